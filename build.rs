@@ -14,8 +14,12 @@ const EXPRESSIONS: &'static RulesList = &[
     "Call     : Expr callee, Vec<Expr> arguments",
     "Get      : Expr object, Token name",
     "Grouping : Expr expression",
+    "Index    : Expr object, Token bracket, Expr index",
+    "IndexSet : Expr object, Token bracket, Expr index, Expr value",
+    "List     : Token bracket, Vec<Expr> elements",
     "Literal  : Literal value",
     "Logical  : Expr left, Token operator, Expr right",
+    "Map      : Token brace, Vec<Expr> keys, Vec<Expr> values",
     "Set      : Expr object, Token name, Expr value",
     "Super    : Token keyword, Token method",
     "This     : Token keyword",
@@ -25,12 +29,16 @@ const EXPRESSIONS: &'static RulesList = &[
 
 const STATEMENTS: &'static RulesList = &[
     "Block      : Vec<Stmt> statements",
-    "Class      : Token name, Option<VariableExpr> superclass, Vec<FunctionStmt> methods",
+    "Class      : Token name, Option<VariableExpr> superclass, Vec<VariableExpr> traits, Vec<FunctionStmt> methods, Vec<FunctionStmt> class_methods, Vec<FunctionStmt> getters, Vec<FunctionStmt> setters",
+    "Enum       : Token name, Vec<Token> variants",
     "Expression : Expr expression",
     "Function   : Token name, Vec<Token> params, Vec<Stmt> body",
     "If         : Expr condition, Stmt then_branch, Stmt else_branch",
+    // one arm for each pattern, then the `else` arm if there is one
+    "Match      : Expr value, Vec<GetExpr> patterns, Vec<Stmt> arms",
     "Print      : Expr expression",
     "Return     : Expr value",
+    "Trait      : Token name, Vec<FunctionStmt> methods",
     "Var        : Token name, Expr initializer",
     "While      : Expr condition, Stmt body",
 ];
@@ -48,7 +56,7 @@ fn define_ast(base: &str, rules: &RulesList) -> anyhow::Result<()> {
     let token = rust::import("crate::tokens", "Token");
 
     let base_snake = &base.to_case(Case::Snake);
-    let base_title = &base.to_case(Case::Title);
+    let base_title = &base.to_case(Case::Pascal);
 
     let tokens: rust::Tokens = quote! {
         mod $(base_snake)_generated {
@@ -61,10 +69,20 @@ fn define_ast(base: &str, rules: &RulesList) -> anyhow::Result<()> {
             }
 
             #[derive(Clone, Debug, Hash, Eq, PartialEq)]
+            #[allow(clippy::large_enum_variant)]
             pub(crate) enum $(base_title) {
                 $(define_enum(base_title, rules))
             }
 
+            impl $(base_title) {
+                #[allow(dead_code)]
+                pub(crate) fn id(&self) -> usize {
+                    match self {
+                        $(define_id(base_title, rules))
+                    }
+                }
+            }
+
             pub(crate) fn walk_$(base_snake)<T>(visitor: &dyn Visitor<T>, $(base_snake): &$(base_title)) -> T {
                 match $(base_snake) {
                     $(define_walk(base_title, rules))
@@ -72,6 +90,12 @@ fn define_ast(base: &str, rules: &RulesList) -> anyhow::Result<()> {
             }
 
             $(define_structs(base_title, rules))
+
+            $(define_walker(base_title, base_snake, rules))
+
+            $(define_mut_visitor(base_title, base_snake, rules))
+
+            $(define_fold(base_title, base_snake, rules))
         }
     };
 
@@ -98,10 +122,12 @@ fn optional_imports(base_snake: &str) -> Tokens {
         }
         "stmt" => {
             let expr = rust::import("crate::expr", "Expr");
+            let get_expr = rust::import("crate::expr", "GetExpr");
             let variable_expr = rust::import("crate::expr", "VariableExpr");
 
             quote! {
                 type Expr = super::$expr;
+                type GetExpr = super::$get_expr;
                 type VariableExpr = super::$variable_expr;
             }
         }
@@ -116,28 +142,28 @@ fn define_visitor_trait(base_title: &str, base_snake: &str, rules: &RulesList) -
         let raw_token_name = rule.split_once(" ").unwrap().0;
 
         let token_snake = &raw_token_name.to_case(Case::Snake);
-        let token_title = &raw_token_name.to_case(Case::Title);
+        let token_title = &raw_token_name.to_case(Case::Pascal);
 
         tokens.append(quote! {
             fn visit_$token_snake(&self, $base_snake: &$token_title$base_title) -> T;
         });
     }
 
-    return tokens;
+    tokens
 }
 
 fn define_enum(base_title: &str, rules: &RulesList) -> Tokens {
     let mut tokens = Tokens::new();
 
     for rule in rules.iter() {
-        let title = &rule.split_once(" ").unwrap().0.to_case(Case::Title);
+        let title = &rule.split_once(" ").unwrap().0.to_case(Case::Pascal);
 
         tokens.append(quote! {
             $title($title$base_title),
         })
     }
 
-    return tokens;
+    tokens
 }
 
 fn define_walk(base_title: &str, rules: &RulesList) -> Tokens {
@@ -147,14 +173,28 @@ fn define_walk(base_title: &str, rules: &RulesList) -> Tokens {
         let raw_token_name = rule.split_once(" ").unwrap().0;
 
         let var = &raw_token_name.to_case(Case::Snake);
-        let class = &raw_token_name.to_case(Case::Title);
+        let class = &raw_token_name.to_case(Case::Pascal);
 
         tokens.append(quote! {
-            $(base_title)::$class(v) => visitor.visit_$var(&v),
+            $(base_title)::$class(v) => visitor.visit_$var(v),
         })
     }
 
-    return tokens;
+    tokens
+}
+
+fn define_id(base_title: &str, rules: &RulesList) -> Tokens {
+    let mut tokens = Tokens::new();
+
+    for rule in rules.iter() {
+        let class = &rule.split_once(" ").unwrap().0.to_case(Case::Pascal);
+
+        tokens.append(quote! {
+            $(base_title)::$class(v) => v.id,
+        })
+    }
+
+    tokens
 }
 
 fn define_structs(base_title: &str, rules: &RulesList) -> Tokens {
@@ -164,7 +204,7 @@ fn define_structs(base_title: &str, rules: &RulesList) -> Tokens {
         tokens.append(define_type(base_title, rule));
     }
 
-    return tokens;
+    tokens
 }
 
 struct Field {
@@ -175,7 +215,7 @@ struct Field {
 fn define_type(base_title: &str, rule: &str) -> Tokens {
     let (raw_name, raw_rules) = rule.split_once(":").unwrap();
 
-    let name_title = &raw_name.trim().to_case(Case::Title);
+    let name_title = &raw_name.trim().to_case(Case::Pascal);
 
     let class = &format!("{}{}", name_title, base_title);
     let fields: Vec<Field> = raw_rules.split(", ").map(parse_field).collect();
@@ -191,9 +231,10 @@ fn define_type(base_title: &str, rule: &str) -> Tokens {
         }
 
         impl $class {
+            #[allow(clippy::too_many_arguments)]
             pub(crate) fn new(id: usize, $(define_constructor_parameters(&fields))) -> $class {
                 $class {
-                    id: id,
+                    id,
                     $(define_constructor_assignment(&fields))
                 }
             }
@@ -217,7 +258,7 @@ fn define_struct_fields(fields: &Vec<Field>) -> Tokens {
         });
     }
 
-    return tokens;
+    tokens
 }
 
 fn define_constructor_parameters(fields: &Vec<Field>) -> Tokens {
@@ -232,7 +273,7 @@ fn define_constructor_parameters(fields: &Vec<Field>) -> Tokens {
         });
     }
 
-    return tokens;
+    tokens
 }
 
 fn define_constructor_assignment(fields: &Vec<Field>) -> Tokens {
@@ -252,7 +293,7 @@ fn define_constructor_assignment(fields: &Vec<Field>) -> Tokens {
         });
     }
 
-    return tokens;
+    tokens
 }
 
 fn parse_field(field: &str) -> Field {
@@ -263,3 +304,293 @@ fn parse_field(field: &str) -> Field {
         name: name.to_string(),
     }
 }
+
+/// How a pass reaches the nodes inside a field, if there are any.
+enum Child {
+    /// `Expr` or `Stmt`, or a node of one, like `VariableExpr`.
+    Node(String),
+    Vec(String),
+    Option(String),
+}
+
+fn child(type_name: &str) -> Option<Child> {
+    let node = |name: &str| match name {
+        "Expr" | "Stmt" => Some(name.to_case(Case::Snake)),
+        name if name.ends_with("Expr") || name.ends_with("Stmt") => Some(name.to_case(Case::Snake)),
+        _ => None,
+    };
+
+    if let Some(inner) = type_name.strip_prefix("Vec<") {
+        node(inner.trim_end_matches('>')).map(Child::Vec)
+    } else if let Some(inner) = type_name.strip_prefix("Option<") {
+        node(inner.trim_end_matches('>')).map(Child::Option)
+    } else {
+        node(type_name).map(Child::Node)
+    }
+}
+
+/// The supertrait of a pass over statements, so it can also visit the
+/// expressions inside them.
+fn expr_supertrait(base_snake: &str, name: &str) -> String {
+    match base_snake {
+        "stmt" => format!(": crate::expr::{}", name),
+        _ => String::new(),
+    }
+}
+
+fn rule_names(rule: &str) -> (String, String) {
+    let raw_name = rule.split_once(" ").unwrap().0;
+    (
+        raw_name.to_case(Case::Pascal),
+        raw_name.to_case(Case::Snake),
+    )
+}
+
+fn rule_fields(rule: &str) -> Vec<Field> {
+    rule.split_once(":")
+        .unwrap()
+        .1
+        .split(", ")
+        .map(parse_field)
+        .collect()
+}
+
+fn define_walker(base_title: &str, base_snake: &str, rules: &RulesList) -> Tokens {
+    let mut methods = Tokens::new();
+    let mut functions = Tokens::new();
+    let mut arms = Tokens::new();
+
+    for rule in rules.iter() {
+        let (title, snake) = &rule_names(rule);
+        let class = &format!("{}{}", title, base_title);
+        let method = &format!("walk_{}_{}", snake, base_snake);
+
+        let mut body = Tokens::new();
+        for field in rule_fields(rule) {
+            let name = &field.name;
+
+            body.append(match child(&field.type_name) {
+                Some(Child::Node(node)) => quote! { walker.walk_$node(&node.$name); },
+                Some(Child::Vec(node)) => quote! {
+                    for v in node.$name.iter() {
+                        walker.walk_$node(v);
+                    }
+                },
+                Some(Child::Option(node)) => quote! {
+                    if let Some(v) = &node.$name {
+                        walker.walk_$node(v);
+                    }
+                },
+                None => quote! {},
+            });
+        }
+
+        let (walker, node) = match body.is_empty() {
+            true => ("_walker", "_node"),
+            false => ("walker", "node"),
+        };
+
+        methods.append(quote! {
+            fn $method(&mut self, node: &$class) {
+                walker::$method(self, node)
+            }
+        });
+        functions.append(quote! {
+            pub(crate) fn $method<W: Walker + ?Sized>($walker: &mut W, $node: &$class) {
+                $body
+            }
+        });
+        arms.append(quote! {
+            $(base_title)::$title(v) => walker.$method(v),
+        });
+    }
+
+    quote! {
+        $("/// Walks the tree without changing it. Every method recurses into the")
+        $("/// node's children by default, so a pass only overrides the nodes it's")
+        $("/// interested in and calls the matching function in `walker` to carry on.")
+        #[allow(dead_code)]
+        pub(crate) trait Walker$(expr_supertrait(base_snake, "Walker")) {
+            fn walk_$base_snake(&mut self, node: &$base_title) {
+                walker::walk_$base_snake(self, node)
+            }
+
+            $methods
+        }
+
+        #[allow(dead_code)]
+        pub(crate) mod walker {
+            use super::*;
+
+            pub(crate) fn walk_$base_snake<W: Walker + ?Sized>(walker: &mut W, node: &$base_title) {
+                match node {
+                    $arms
+                }
+            }
+
+            $functions
+        }
+    }
+}
+
+fn define_mut_visitor(base_title: &str, base_snake: &str, rules: &RulesList) -> Tokens {
+    let mut methods = Tokens::new();
+    let mut functions = Tokens::new();
+    let mut arms = Tokens::new();
+
+    for rule in rules.iter() {
+        let (title, snake) = &rule_names(rule);
+        let class = &format!("{}{}", title, base_title);
+        let method = &format!("visit_{}_{}_mut", snake, base_snake);
+
+        let mut body = Tokens::new();
+        for field in rule_fields(rule) {
+            let name = &field.name;
+
+            body.append(match child(&field.type_name) {
+                Some(Child::Node(node)) => {
+                    quote! { visitor.visit_$(node)_mut(&mut node.$name); }
+                }
+                Some(Child::Vec(node)) => quote! {
+                    for v in node.$name.iter_mut() {
+                        visitor.visit_$(node)_mut(v);
+                    }
+                },
+                Some(Child::Option(node)) => quote! {
+                    if let Some(v) = &mut node.$name {
+                        visitor.visit_$(node)_mut(v);
+                    }
+                },
+                None => quote! {},
+            });
+        }
+
+        let (visitor, node) = match body.is_empty() {
+            true => ("_visitor", "_node"),
+            false => ("visitor", "node"),
+        };
+
+        methods.append(quote! {
+            fn $method(&mut self, node: &mut $class) {
+                mut_visitor::$method(self, node)
+            }
+        });
+        functions.append(quote! {
+            pub(crate) fn $method<V: MutVisitor + ?Sized>($visitor: &mut V, $node: &mut $class) {
+                $body
+            }
+        });
+        arms.append(quote! {
+            $(base_title)::$title(v) => visitor.$method(v),
+        });
+    }
+
+    quote! {
+        $("/// Walks the tree, changing nodes in place. Like `Walker`, every method")
+        $("/// recurses by default and the functions in `mut_visitor` carry on.")
+        pub(crate) trait MutVisitor$(expr_supertrait(base_snake, "MutVisitor")) {
+            fn visit_$(base_snake)_mut(&mut self, node: &mut $base_title) {
+                mut_visitor::visit_$(base_snake)_mut(self, node)
+            }
+
+            $methods
+        }
+
+        pub(crate) mod mut_visitor {
+            use super::*;
+
+            pub(crate) fn visit_$(base_snake)_mut<V: MutVisitor + ?Sized>(visitor: &mut V, node: &mut $base_title) {
+                match node {
+                    $arms
+                }
+            }
+
+            $functions
+        }
+    }
+}
+
+fn define_fold(base_title: &str, base_snake: &str, rules: &RulesList) -> Tokens {
+    let mut methods = Tokens::new();
+    let mut functions = Tokens::new();
+    let mut arms = Tokens::new();
+
+    for rule in rules.iter() {
+        let (title, snake) = &rule_names(rule);
+        let class = &format!("{}{}", title, base_title);
+        let method = &format!("fold_{}_{}", snake, base_snake);
+
+        let fields = rule_fields(rule);
+        let mut assignments = Tokens::new();
+        for field in fields.iter() {
+            let name = &field.name;
+
+            assignments.append(match child(&field.type_name) {
+                Some(Child::Node(node)) if node == "expr" || node == "stmt" => {
+                    quote! { $name: Box::new(folder.fold_$node(*node.$name)), }
+                }
+                Some(Child::Node(node)) => quote! { $name: folder.fold_$node(node.$name), },
+                Some(Child::Vec(node)) => quote! {
+                    $name: node.$name.into_iter().map(|v| folder.fold_$node(v)).collect(),
+                },
+                Some(Child::Option(node)) => quote! {
+                    $name: node.$name.map(|v| folder.fold_$node(v)),
+                },
+                None => quote! { $name: node.$name, },
+            });
+        }
+
+        let folds_children = fields.iter().any(|f| child(&f.type_name).is_some());
+
+        methods.append(quote! {
+            fn $method(&mut self, node: $class) -> $class {
+                fold::$method(self, node)
+            }
+        });
+        functions.append(match folds_children {
+            true => quote! {
+                pub(crate) fn $method<F: Fold + ?Sized>(folder: &mut F, node: $class) -> $class {
+                    $class {
+                        id: node.id,
+                        $assignments
+                    }
+                }
+            },
+            false => quote! {
+                pub(crate) fn $method<F: Fold + ?Sized>(_folder: &mut F, node: $class) -> $class {
+                    node
+                }
+            },
+        });
+        arms.append(quote! {
+            $(base_title)::$title(v) => $(base_title)::$title(folder.$method(v)),
+        });
+    }
+
+    quote! {
+        $("/// Rebuilds the tree from the bottom up. By default every node is rebuilt")
+        $("/// from its folded children; override `fold_expr` or `fold_stmt` to")
+        $("/// replace a node with a different kind of node.")
+        #[allow(dead_code)]
+        pub(crate) trait Fold$(expr_supertrait(base_snake, "Fold")) {
+            fn fold_$base_snake(&mut self, node: $base_title) -> $base_title {
+                fold::fold_$base_snake(self, node)
+            }
+
+            $methods
+        }
+
+        #[allow(dead_code)]
+        pub(crate) mod fold {
+            use super::*;
+
+            pub(crate) fn fold_$base_snake<F: Fold + ?Sized>(folder: &mut F, node: $base_title) -> $base_title {
+                match node {
+                    $arms
+                }
+            }
+
+            $functions
+        }
+    }
+}
@@ -0,0 +1,4 @@
+@echo off
+rem Starts the debug adapter. Set RLOX to the interpreter if it isn't on PATH.
+if "%RLOX%"=="" set RLOX=rlox
+"%RLOX%" debug %*
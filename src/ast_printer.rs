@@ -1,78 +1,683 @@
-use crate::expr::*;
+use crate::expr::{self, *};
+use crate::json::Json;
+use crate::parser::Spans;
+use crate::stmt::{self, *};
+use crate::tokens::{Literal, Token};
 
-#[allow(dead_code)]
-pub(crate) fn print(expression: &Expr) -> String {
-    walk_expr(&mut AstPrinter, expression)
+/// Prints each statement as a Lisp-style S-expression, one per line.
+pub(crate) fn print(statements: &[Stmt]) -> String {
+    statements
+        .iter()
+        .map(|s| format!("{}\n", walk_stmt(&AstPrinter, s)))
+        .collect()
+}
+
+/// Prints the statements as a JSON array of nodes, each with its id, the
+/// lines it spans and a field for every part of its grammar rule.
+pub(crate) fn print_json(statements: &[Stmt], spans: &Spans) -> String {
+    let printer = JsonPrinter { spans };
+
+    Json::Array(statements.iter().map(|s| walk_stmt(&printer, s)).collect()).to_string()
+}
+
+/// Prints each token on its own line as `(Type "lexeme" line:column)`.
+pub(crate) fn print_tokens<'a>(tokens: impl Iterator<Item = &'a Token>) -> String {
+    tokens
+        .map(|t| {
+            format!(
+                "({:?} \"{}\" {}:{})\n",
+                t.token_type, t.lexeme, t.line_number, t.column
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn print_tokens_json<'a>(tokens: impl Iterator<Item = &'a Token>) -> String {
+    Json::Array(tokens.map(token).collect()).to_string()
 }
 
 struct AstPrinter;
 
 impl AstPrinter {
-    fn parenthesize(&self, name: &str, exprs: Vec<&Expr>) -> String {
+    fn parenthesize(&self, name: &str, parts: Vec<String>) -> String {
         let mut builder = String::new();
 
         builder.push('(');
         builder.push_str(name);
 
-        for expr in exprs {
+        for part in parts {
             builder.push(' ');
-            builder.push_str(&walk_expr(self, expr))
+            builder.push_str(&part)
         }
         builder.push(')');
 
         return builder;
     }
+
+    fn expr(&self, expr: &Expr) -> String {
+        walk_expr(self, expr)
+    }
+
+    fn stmt(&self, stmt: &Stmt) -> String {
+        walk_stmt(self, stmt)
+    }
+
+    fn function(&self, name: &str, stmt: &FunctionStmt) -> String {
+        let params: Vec<&str> = stmt.params.iter().map(|p| p.lexeme.as_str()).collect();
+
+        let mut parts = vec![
+            stmt.name.lexeme.to_string(),
+            format!("({})", params.join(" ")),
+        ];
+        parts.extend(stmt.body.iter().map(|s| self.stmt(s)));
+
+        self.parenthesize(name, parts)
+    }
 }
 
-impl Visitor<String> for AstPrinter {
+impl expr::Visitor<String> for AstPrinter {
+    fn visit_assign(&self, expr: &AssignExpr) -> String {
+        self.parenthesize(
+            "assign",
+            vec![expr.name.lexeme.to_string(), self.expr(&expr.value)],
+        )
+    }
+
     fn visit_binary(&self, expr: &BinaryExpr) -> String {
-        self.parenthesize(&expr.operator.lexeme, vec![&expr.left, &expr.right])
+        self.parenthesize(
+            &expr.operator.lexeme,
+            vec![self.expr(&expr.left), self.expr(&expr.right)],
+        )
     }
 
-    fn visit_literal(&self, expr: &LiteralExpr) -> String {
-        format!("{}", &expr.value)
+    fn visit_call(&self, expr: &CallExpr) -> String {
+        let mut parts = vec![self.expr(&expr.callee)];
+        parts.extend(expr.arguments.iter().map(|a| self.expr(a)));
+
+        self.parenthesize("call", parts)
+    }
+
+    fn visit_get(&self, expr: &GetExpr) -> String {
+        self.parenthesize(
+            "get",
+            vec![self.expr(&expr.object), expr.name.lexeme.to_string()],
+        )
     }
 
     fn visit_grouping(&self, expr: &GroupingExpr) -> String {
-        self.parenthesize("group", vec![&expr.expression])
+        self.parenthesize("group", vec![self.expr(&expr.expression)])
     }
 
-    fn visit_unary(&self, expr: &UnaryExpr) -> String {
-        self.parenthesize(&expr.operator.lexeme, vec![&expr.right])
+    fn visit_index(&self, expr: &IndexExpr) -> String {
+        self.parenthesize(
+            "index",
+            vec![self.expr(&expr.object), self.expr(&expr.index)],
+        )
     }
 
-    fn visit_variable(&self, expr: &VariableExpr) -> String {
-        expr.name.to_string()
+    fn visit_index_set(&self, expr: &IndexSetExpr) -> String {
+        self.parenthesize(
+            "index-set",
+            vec![
+                self.expr(&expr.object),
+                self.expr(&expr.index),
+                self.expr(&expr.value),
+            ],
+        )
     }
 
-    fn visit_assign(&self, expr: &AssignExpr) -> String {
-        self.parenthesize(&format!("let {}", expr.name), vec![&expr.value])
+    fn visit_list(&self, expr: &ListExpr) -> String {
+        self.parenthesize("list", expr.elements.iter().map(|e| self.expr(e)).collect())
     }
 
-    fn visit_logical(&self, expr: &LogicalExpr) -> String {
-        self.parenthesize(&expr.operator.lexeme, vec![&expr.left, &expr.right])
+    fn visit_map(&self, expr: &MapExpr) -> String {
+        let entries = expr
+            .keys
+            .iter()
+            .zip(expr.values.iter())
+            .map(|(k, v)| self.parenthesize("entry", vec![self.expr(k), self.expr(v)]))
+            .collect();
+        self.parenthesize("map", entries)
     }
 
-    fn visit_call(&self, expr: &CallExpr) -> String {
-        let func = walk_expr(self, &expr.callee);
-        self.parenthesize(&func, expr.arguments.iter().collect())
+    fn visit_literal(&self, expr: &LiteralExpr) -> String {
+        match &expr.value {
+            Literal::String(s) => format!("\"{}\"", s),
+            value => value.to_string(),
+        }
     }
 
-    fn visit_get(&self, expr: &GetExpr) -> String {
-        let object = walk_expr(self, &expr.object);
-        format!("{}.{}", object, expr.name)
+    fn visit_logical(&self, expr: &LogicalExpr) -> String {
+        self.parenthesize(
+            &expr.operator.lexeme,
+            vec![self.expr(&expr.left), self.expr(&expr.right)],
+        )
     }
 
     fn visit_set(&self, expr: &SetExpr) -> String {
-        let object = walk_expr(self, &expr.object);
-        format!("{}.{} = ", object, expr.name)
+        self.parenthesize(
+            "set",
+            vec![
+                self.expr(&expr.object),
+                expr.name.lexeme.to_string(),
+                self.expr(&expr.value),
+            ],
+        )
+    }
+
+    fn visit_super(&self, expr: &SuperExpr) -> String {
+        self.parenthesize("super", vec![expr.method.lexeme.to_string()])
     }
 
     fn visit_this(&self, expr: &ThisExpr) -> String {
         expr.keyword.lexeme.to_string()
     }
 
-    fn visit_super(&self, expr: &SuperExpr) -> String {
-        expr.keyword.lexeme.to_string()
+    fn visit_unary(&self, expr: &UnaryExpr) -> String {
+        self.parenthesize(&expr.operator.lexeme, vec![self.expr(&expr.right)])
+    }
+
+    fn visit_variable(&self, expr: &VariableExpr) -> String {
+        expr.name.lexeme.to_string()
+    }
+}
+
+impl stmt::Visitor<String> for AstPrinter {
+    fn visit_block(&self, stmt: &BlockStmt) -> String {
+        self.parenthesize(
+            "block",
+            stmt.statements.iter().map(|s| self.stmt(s)).collect(),
+        )
+    }
+
+    fn visit_class(&self, stmt: &ClassStmt) -> String {
+        let mut parts = vec![stmt.name.lexeme.to_string()];
+        if let Some(superclass) = &stmt.superclass {
+            parts.push(format!("(< {})", superclass.name.lexeme));
+        }
+        if !stmt.traits.is_empty() {
+            let traits: Vec<String> = stmt.traits.iter().map(|t| t.name.lexeme.clone()).collect();
+            parts.push(self.parenthesize("with", traits));
+        }
+        parts.extend(stmt.methods.iter().map(|m| self.function("method", m)));
+        parts.extend(
+            stmt.class_methods
+                .iter()
+                .map(|m| self.function("class-method", m)),
+        );
+        parts.extend(stmt.getters.iter().map(|m| self.function("getter", m)));
+        parts.extend(stmt.setters.iter().map(|m| self.function("setter", m)));
+
+        self.parenthesize("class", parts)
+    }
+
+    fn visit_enum(&self, stmt: &EnumStmt) -> String {
+        let mut parts = vec![stmt.name.lexeme.to_string()];
+        parts.extend(stmt.variants.iter().map(|v| v.lexeme.clone()));
+
+        self.parenthesize("enum", parts)
+    }
+
+    fn visit_expression(&self, stmt: &ExpressionStmt) -> String {
+        self.parenthesize("expr", vec![self.expr(&stmt.expression)])
+    }
+
+    fn visit_function(&self, stmt: &FunctionStmt) -> String {
+        self.function("fun", stmt)
+    }
+
+    fn visit_if(&self, stmt: &IfStmt) -> String {
+        self.parenthesize(
+            "if",
+            vec![
+                self.expr(&stmt.condition),
+                self.stmt(&stmt.then_branch),
+                self.stmt(&stmt.else_branch),
+            ],
+        )
+    }
+
+    fn visit_match(&self, stmt: &MatchStmt) -> String {
+        let mut parts = vec![self.expr(&stmt.value)];
+        for (i, arm) in stmt.arms.iter().enumerate() {
+            parts.push(match stmt.patterns.get(i) {
+                Some(pattern) => self.parenthesize(
+                    "case",
+                    vec![expr::Visitor::visit_get(self, pattern), self.stmt(arm)],
+                ),
+                None => self.parenthesize("else", vec![self.stmt(arm)]),
+            });
+        }
+
+        self.parenthesize("match", parts)
+    }
+
+    fn visit_print(&self, stmt: &PrintStmt) -> String {
+        self.parenthesize("print", vec![self.expr(&stmt.expression)])
+    }
+
+    fn visit_return(&self, stmt: &ReturnStmt) -> String {
+        self.parenthesize("return", vec![self.expr(&stmt.value)])
+    }
+
+    fn visit_trait(&self, stmt: &TraitStmt) -> String {
+        let mut parts = vec![stmt.name.lexeme.to_string()];
+        parts.extend(stmt.methods.iter().map(|m| self.function("method", m)));
+
+        self.parenthesize("trait", parts)
+    }
+
+    fn visit_var(&self, stmt: &VarStmt) -> String {
+        self.parenthesize(
+            "var",
+            vec![stmt.name.lexeme.to_string(), self.expr(&stmt.initializer)],
+        )
+    }
+
+    fn visit_while(&self, stmt: &WhileStmt) -> String {
+        self.parenthesize(
+            "while",
+            vec![self.expr(&stmt.condition), self.stmt(&stmt.body)],
+        )
+    }
+}
+
+struct JsonPrinter<'a> {
+    spans: &'a Spans,
+}
+
+impl JsonPrinter<'_> {
+    fn node(&self, kind: &str, id: usize, fields: Vec<(&str, Json)>) -> Json {
+        let mut entries = vec![("node", kind.into()), ("id", id.into())];
+
+        if let Some(span) = self.spans.get(id) {
+            entries.push((
+                "span",
+                Json::object(vec![("start", span.start.into()), ("end", span.end.into())]),
+            ));
+        }
+        if self.spans.is_synthetic(id) {
+            entries.push(("synthetic", true.into()));
+        }
+
+        entries.extend(fields);
+        Json::object(entries)
+    }
+
+    fn expr(&self, expr: &Expr) -> Json {
+        walk_expr(self, expr)
+    }
+
+    fn stmt(&self, stmt: &Stmt) -> Json {
+        walk_stmt(self, stmt)
+    }
+
+    fn stmts(&self, stmts: &[Stmt]) -> Json {
+        Json::Array(stmts.iter().map(|s| self.stmt(s)).collect())
+    }
+}
+
+fn token(token: &Token) -> Json {
+    Json::object(vec![
+        ("type", format!("{:?}", token.token_type).into()),
+        ("lexeme", token.lexeme.as_str().into()),
+        ("line", token.line_number.into()),
+        ("column", token.column.into()),
+    ])
+}
+
+fn literal(value: &Literal) -> Json {
+    match value {
+        Literal::Nil => Json::Null,
+        Literal::Boolean(b) => Json::Bool(*b),
+        Literal::Number(n) => Json::Number(*n),
+        value => value.to_string().into(),
+    }
+}
+
+impl expr::Visitor<Json> for JsonPrinter<'_> {
+    fn visit_assign(&self, expr: &AssignExpr) -> Json {
+        self.node(
+            "Assign",
+            expr.id,
+            vec![
+                ("name", token(&expr.name)),
+                ("value", self.expr(&expr.value)),
+            ],
+        )
+    }
+
+    fn visit_binary(&self, expr: &BinaryExpr) -> Json {
+        self.node(
+            "Binary",
+            expr.id,
+            vec![
+                ("left", self.expr(&expr.left)),
+                ("operator", token(&expr.operator)),
+                ("right", self.expr(&expr.right)),
+            ],
+        )
+    }
+
+    fn visit_call(&self, expr: &CallExpr) -> Json {
+        self.node(
+            "Call",
+            expr.id,
+            vec![
+                ("callee", self.expr(&expr.callee)),
+                (
+                    "arguments",
+                    Json::Array(expr.arguments.iter().map(|a| self.expr(a)).collect()),
+                ),
+            ],
+        )
+    }
+
+    fn visit_get(&self, expr: &GetExpr) -> Json {
+        self.node(
+            "Get",
+            expr.id,
+            vec![
+                ("object", self.expr(&expr.object)),
+                ("name", token(&expr.name)),
+            ],
+        )
+    }
+
+    fn visit_index(&self, expr: &IndexExpr) -> Json {
+        self.node(
+            "Index",
+            expr.id,
+            vec![
+                ("object", self.expr(&expr.object)),
+                ("bracket", token(&expr.bracket)),
+                ("index", self.expr(&expr.index)),
+            ],
+        )
+    }
+
+    fn visit_index_set(&self, expr: &IndexSetExpr) -> Json {
+        self.node(
+            "IndexSet",
+            expr.id,
+            vec![
+                ("object", self.expr(&expr.object)),
+                ("bracket", token(&expr.bracket)),
+                ("index", self.expr(&expr.index)),
+                ("value", self.expr(&expr.value)),
+            ],
+        )
+    }
+
+    fn visit_list(&self, expr: &ListExpr) -> Json {
+        self.node(
+            "List",
+            expr.id,
+            vec![
+                ("bracket", token(&expr.bracket)),
+                (
+                    "elements",
+                    Json::Array(expr.elements.iter().map(|e| self.expr(e)).collect()),
+                ),
+            ],
+        )
+    }
+
+    fn visit_map(&self, expr: &MapExpr) -> Json {
+        self.node(
+            "Map",
+            expr.id,
+            vec![
+                ("brace", token(&expr.brace)),
+                (
+                    "keys",
+                    Json::Array(expr.keys.iter().map(|e| self.expr(e)).collect()),
+                ),
+                (
+                    "values",
+                    Json::Array(expr.values.iter().map(|e| self.expr(e)).collect()),
+                ),
+            ],
+        )
+    }
+
+    fn visit_grouping(&self, expr: &GroupingExpr) -> Json {
+        self.node(
+            "Grouping",
+            expr.id,
+            vec![("expression", self.expr(&expr.expression))],
+        )
+    }
+
+    fn visit_literal(&self, expr: &LiteralExpr) -> Json {
+        self.node("Literal", expr.id, vec![("value", literal(&expr.value))])
+    }
+
+    fn visit_logical(&self, expr: &LogicalExpr) -> Json {
+        self.node(
+            "Logical",
+            expr.id,
+            vec![
+                ("left", self.expr(&expr.left)),
+                ("operator", token(&expr.operator)),
+                ("right", self.expr(&expr.right)),
+            ],
+        )
+    }
+
+    fn visit_set(&self, expr: &SetExpr) -> Json {
+        self.node(
+            "Set",
+            expr.id,
+            vec![
+                ("object", self.expr(&expr.object)),
+                ("name", token(&expr.name)),
+                ("value", self.expr(&expr.value)),
+            ],
+        )
+    }
+
+    fn visit_super(&self, expr: &SuperExpr) -> Json {
+        self.node(
+            "Super",
+            expr.id,
+            vec![
+                ("keyword", token(&expr.keyword)),
+                ("method", token(&expr.method)),
+            ],
+        )
+    }
+
+    fn visit_this(&self, expr: &ThisExpr) -> Json {
+        self.node("This", expr.id, vec![("keyword", token(&expr.keyword))])
+    }
+
+    fn visit_unary(&self, expr: &UnaryExpr) -> Json {
+        self.node(
+            "Unary",
+            expr.id,
+            vec![
+                ("operator", token(&expr.operator)),
+                ("right", self.expr(&expr.right)),
+            ],
+        )
+    }
+
+    fn visit_variable(&self, expr: &VariableExpr) -> Json {
+        self.node("Variable", expr.id, vec![("name", token(&expr.name))])
+    }
+}
+
+impl stmt::Visitor<Json> for JsonPrinter<'_> {
+    fn visit_block(&self, stmt: &BlockStmt) -> Json {
+        self.node(
+            "Block",
+            stmt.id,
+            vec![("statements", self.stmts(&stmt.statements))],
+        )
+    }
+
+    fn visit_class(&self, stmt: &ClassStmt) -> Json {
+        let superclass = match &stmt.superclass {
+            Some(superclass) => expr::Visitor::visit_variable(self, superclass),
+            None => Json::Null,
+        };
+        let traits = stmt
+            .traits
+            .iter()
+            .map(|t| expr::Visitor::visit_variable(self, t))
+            .collect();
+        let methods = stmt
+            .methods
+            .iter()
+            .map(|m| self.visit_function(m))
+            .collect();
+        let class_methods = stmt
+            .class_methods
+            .iter()
+            .map(|m| self.visit_function(m))
+            .collect();
+        let getters = stmt
+            .getters
+            .iter()
+            .map(|m| self.visit_function(m))
+            .collect();
+        let setters = stmt
+            .setters
+            .iter()
+            .map(|m| self.visit_function(m))
+            .collect();
+
+        self.node(
+            "Class",
+            stmt.id,
+            vec![
+                ("name", token(&stmt.name)),
+                ("superclass", superclass),
+                ("traits", Json::Array(traits)),
+                ("methods", Json::Array(methods)),
+                ("class_methods", Json::Array(class_methods)),
+                ("getters", Json::Array(getters)),
+                ("setters", Json::Array(setters)),
+            ],
+        )
+    }
+
+    fn visit_enum(&self, stmt: &EnumStmt) -> Json {
+        self.node(
+            "Enum",
+            stmt.id,
+            vec![
+                ("name", token(&stmt.name)),
+                (
+                    "variants",
+                    Json::Array(stmt.variants.iter().map(token).collect()),
+                ),
+            ],
+        )
+    }
+
+    fn visit_expression(&self, stmt: &ExpressionStmt) -> Json {
+        self.node(
+            "Expression",
+            stmt.id,
+            vec![("expression", self.expr(&stmt.expression))],
+        )
+    }
+
+    fn visit_function(&self, stmt: &FunctionStmt) -> Json {
+        self.node(
+            "Function",
+            stmt.id,
+            vec![
+                ("name", token(&stmt.name)),
+                (
+                    "params",
+                    Json::Array(stmt.params.iter().map(token).collect()),
+                ),
+                ("body", self.stmts(&stmt.body)),
+            ],
+        )
+    }
+
+    fn visit_if(&self, stmt: &IfStmt) -> Json {
+        self.node(
+            "If",
+            stmt.id,
+            vec![
+                ("condition", self.expr(&stmt.condition)),
+                ("then_branch", self.stmt(&stmt.then_branch)),
+                ("else_branch", self.stmt(&stmt.else_branch)),
+            ],
+        )
+    }
+
+    fn visit_match(&self, stmt: &MatchStmt) -> Json {
+        let patterns = stmt
+            .patterns
+            .iter()
+            .map(|p| expr::Visitor::visit_get(self, p))
+            .collect();
+
+        self.node(
+            "Match",
+            stmt.id,
+            vec![
+                ("value", self.expr(&stmt.value)),
+                ("patterns", Json::Array(patterns)),
+                ("arms", self.stmts(&stmt.arms)),
+            ],
+        )
+    }
+
+    fn visit_print(&self, stmt: &PrintStmt) -> Json {
+        self.node(
+            "Print",
+            stmt.id,
+            vec![("expression", self.expr(&stmt.expression))],
+        )
+    }
+
+    fn visit_return(&self, stmt: &ReturnStmt) -> Json {
+        self.node("Return", stmt.id, vec![("value", self.expr(&stmt.value))])
+    }
+
+    fn visit_trait(&self, stmt: &TraitStmt) -> Json {
+        let methods = stmt
+            .methods
+            .iter()
+            .map(|m| self.visit_function(m))
+            .collect();
+
+        self.node(
+            "Trait",
+            stmt.id,
+            vec![
+                ("name", token(&stmt.name)),
+                ("methods", Json::Array(methods)),
+            ],
+        )
+    }
+
+    fn visit_var(&self, stmt: &VarStmt) -> Json {
+        self.node(
+            "Var",
+            stmt.id,
+            vec![
+                ("name", token(&stmt.name)),
+                ("initializer", self.expr(&stmt.initializer)),
+            ],
+        )
+    }
+
+    fn visit_while(&self, stmt: &WhileStmt) -> Json {
+        self.node(
+            "While",
+            stmt.id,
+            vec![
+                ("condition", self.expr(&stmt.condition)),
+                ("body", self.stmt(&stmt.body)),
+            ],
+        )
     }
 }
@@ -0,0 +1,602 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::{self, Write},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use crate::{
+    environment::Environment,
+    init_globals,
+    interpreter::{self, Debugger, Options, RuntimeError},
+    json::{self, Json},
+    native::Streams,
+    parser,
+    resolver::{self, private},
+    tokens::{Literal, LoxInstance, Tokens},
+};
+
+/// Runs a Debug Adapter Protocol server on stdin/stdout. The script to debug
+/// is taken from the command line or, failing that, from the `program`
+/// argument of the client's launch request, and runs with the same options as
+/// it would from the command line.
+pub(crate) fn serve(script: Option<String>, options: Options) -> Result<(), String> {
+    let (sender, messages) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        while let Some(message) = json::read_message(&mut stdin) {
+            // the protocol has no way to answer a message without a `seq`
+            let message = match message {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let session = Rc::new(Session::new(messages));
+    let launch = session.configure()?;
+
+    let path = match script.or(launch.program) {
+        Some(path) => path,
+        None => return Err("No program given to debug".into()),
+    };
+    session.path.replace(source_path(&path));
+    if launch.stop_on_entry {
+        session.mode.set(StepMode::StepIn);
+    }
+
+    let exit_code = match run(&path, session.clone(), options) {
+        Ok(_) => 0,
+        Err(RuntimeError::Exit(status)) => status,
+        Err(error) => {
+            Vec::from(error)
+                .iter()
+                .for_each(|e| session.output("stderr", &format!("{}\n", e)));
+            1
+        }
+    };
+
+    session.event("exited", Json::object(vec![("exitCode", exit_code.into())]));
+    session.event("terminated", Json::object(vec![]));
+    session.wait_for_disconnect();
+
+    Ok(())
+}
+
+fn run(path: &str, session: Rc<Session>, options: Options) -> Result<(), RuntimeError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| vec![format!("Failed to read file '{}': '{}'", path, e)])?;
+
+    let tokens: Tokens = contents.parse()?;
+    let (mut statements, spans) = parser::parse_with_spans(tokens)?;
+    private::scope_to_classes(&mut statements);
+    let locals = resolver::resolve_locals(&statements)?;
+
+    // stdin carries the protocol, so the script reads nothing: `readLine()`
+    // and `input()` return nil
+    let streams = Streams::new(
+        Output(session.clone(), "stdout"),
+        Output(session.clone(), "stderr"),
+        io::empty(),
+    );
+
+    interpreter::debug(
+        init_globals(),
+        locals,
+        spans,
+        &statements,
+        options,
+        session,
+        streams,
+    )
+}
+
+/// The path a source is known by, so breakpoints set on it match the script
+/// however either was named.
+fn source_path(path: &str) -> String {
+    fs::canonicalize(path)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// Sends what the script writes to the client as output events of a category.
+struct Output(Rc<Session>, &'static str);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.output(self.1, &String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StepMode {
+    Continue,
+    Pause,
+    StepIn,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+enum Action {
+    None,
+    Resume(StepMode),
+    Launch(Launch),
+    ConfigurationDone,
+    Disconnect,
+}
+
+#[derive(Default)]
+struct Launch {
+    program: Option<String>,
+    stop_on_entry: bool,
+}
+
+struct Frame {
+    name: String,
+    line: usize,
+    environment: Option<Environment>,
+    /// Whether the script has stopped on `line` already, so statements nested
+    /// in the same line don't stop it again.
+    stopped: bool,
+}
+
+enum Handle {
+    Locals(Environment),
+    Globals(Environment),
+    Instance(LoxInstance),
+}
+
+struct Session {
+    messages: Receiver<Json>,
+    seq: Cell<usize>,
+    path: RefCell<String>,
+    /// Breakpoint lines by the `source_path` of the file they're set in.
+    breakpoints: RefCell<HashMap<String, BTreeSet<usize>>>,
+    mode: Cell<StepMode>,
+    frames: RefCell<Vec<Frame>>,
+    handles: RefCell<Vec<Handle>>,
+}
+
+impl Session {
+    fn new(messages: Receiver<Json>) -> Session {
+        Session {
+            messages,
+            seq: Cell::new(1),
+            path: RefCell::new(String::new()),
+            breakpoints: RefCell::new(HashMap::new()),
+            mode: Cell::new(StepMode::Continue),
+            frames: RefCell::new(vec![Frame {
+                name: "<script>".to_string(),
+                line: 0,
+                environment: None,
+                stopped: false,
+            }]),
+            handles: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Handles requests until the client has both launched and finished
+    /// configuring breakpoints.
+    fn configure(&self) -> Result<Launch, String> {
+        let mut launch = None;
+        let mut configured = false;
+
+        while launch.is_none() || !configured {
+            let message = self
+                .messages
+                .recv()
+                .map_err(|_| "Client disconnected before launch".to_string())?;
+
+            match self.handle(&message) {
+                Action::Launch(l) => launch = Some(l),
+                Action::ConfigurationDone => configured = true,
+                Action::Disconnect => return Err("Client disconnected before launch".into()),
+                _ => (),
+            }
+        }
+
+        Ok(launch.unwrap())
+    }
+
+    fn wait_for_disconnect(&self) {
+        while let Ok(message) = self.messages.recv() {
+            if let Action::Disconnect = self.handle(&message) {
+                return;
+            }
+        }
+    }
+
+    fn send(&self, mut message: Vec<(&str, Json)>) {
+        let seq = self.seq.get();
+        self.seq.set(seq + 1);
+        message.insert(0, ("seq", seq.into()));
+
+        json::write_message(&mut io::stdout().lock(), &Json::object(message));
+    }
+
+    fn event(&self, event: &str, body: Json) {
+        self.send(vec![
+            ("type", "event".into()),
+            ("event", event.into()),
+            ("body", body),
+        ]);
+    }
+
+    fn respond(&self, request: &Json, body: Json) {
+        self.send(vec![
+            ("type", "response".into()),
+            (
+                "request_seq",
+                request.get("seq").cloned().unwrap_or(Json::Null),
+            ),
+            ("success", true.into()),
+            (
+                "command",
+                request.get("command").cloned().unwrap_or(Json::Null),
+            ),
+            ("body", body),
+        ]);
+    }
+
+    fn output(&self, category: &str, text: &str) {
+        self.event(
+            "output",
+            Json::object(vec![("category", category.into()), ("output", text.into())]),
+        );
+    }
+
+    fn handle(&self, request: &Json) -> Action {
+        let empty = Json::object(vec![]);
+        let arguments = request.get("arguments").unwrap_or(&empty);
+
+        match request.get("command").and_then(Json::as_str).unwrap_or("") {
+            "initialize" => {
+                self.respond(
+                    request,
+                    Json::object(vec![
+                        ("supportsConfigurationDoneRequest", true.into()),
+                        ("supportsSingleThreadExecutionRequests", false.into()),
+                    ]),
+                );
+                self.event("initialized", Json::object(vec![]));
+                Action::None
+            }
+            "launch" => {
+                self.respond(request, Json::object(vec![]));
+                Action::Launch(Launch {
+                    program: arguments
+                        .get("program")
+                        .and_then(Json::as_str)
+                        .map(String::from),
+                    stop_on_entry: arguments
+                        .get("stopOnEntry")
+                        .and_then(Json::as_bool)
+                        .unwrap_or(false),
+                })
+            }
+            "setBreakpoints" => {
+                let path = arguments
+                    .get("source")
+                    .and_then(|source| source.get("path"))
+                    .and_then(Json::as_str);
+                let lines: Vec<usize> = arguments
+                    .get("breakpoints")
+                    .map_or(&[][..], Json::as_array)
+                    .iter()
+                    .filter_map(|b| b.get("line").and_then(Json::as_usize))
+                    .collect();
+
+                // a breakpoint without a source can't be matched to any line
+                if let Some(path) = path {
+                    self.breakpoints
+                        .borrow_mut()
+                        .insert(source_path(path), lines.iter().cloned().collect());
+                }
+
+                let verified = lines
+                    .iter()
+                    .map(|&line| {
+                        Json::object(vec![
+                            ("verified", path.is_some().into()),
+                            ("line", line.into()),
+                        ])
+                    })
+                    .collect();
+                self.respond(
+                    request,
+                    Json::object(vec![("breakpoints", Json::Array(verified))]),
+                );
+                Action::None
+            }
+            "configurationDone" => {
+                self.respond(request, Json::object(vec![]));
+                Action::ConfigurationDone
+            }
+            "threads" => {
+                let thread = Json::object(vec![("id", 1.into()), ("name", "main".into())]);
+                self.respond(
+                    request,
+                    Json::object(vec![("threads", Json::Array(vec![thread]))]),
+                );
+                Action::None
+            }
+            "stackTrace" => {
+                self.respond(request, self.stack_trace());
+                Action::None
+            }
+            "scopes" => {
+                let frame = arguments
+                    .get("frameId")
+                    .and_then(Json::as_usize)
+                    .unwrap_or(0);
+                self.respond(request, self.scopes(frame));
+                Action::None
+            }
+            "variables" => {
+                let reference = arguments
+                    .get("variablesReference")
+                    .and_then(Json::as_usize)
+                    .unwrap_or(0);
+                self.respond(request, self.variables(reference));
+                Action::None
+            }
+            "continue" => {
+                self.respond(
+                    request,
+                    Json::object(vec![("allThreadsContinued", true.into())]),
+                );
+                Action::Resume(StepMode::Continue)
+            }
+            "next" => {
+                self.respond(request, Json::object(vec![]));
+                Action::Resume(StepMode::StepOver(self.depth()))
+            }
+            "stepIn" => {
+                self.respond(request, Json::object(vec![]));
+                Action::Resume(StepMode::StepIn)
+            }
+            "stepOut" => {
+                self.respond(request, Json::object(vec![]));
+                Action::Resume(StepMode::StepOut(self.depth()))
+            }
+            "pause" => {
+                self.respond(request, Json::object(vec![]));
+                self.mode.set(StepMode::Pause);
+                Action::None
+            }
+            "disconnect" | "terminate" => {
+                self.respond(request, Json::object(vec![]));
+                Action::Disconnect
+            }
+            _ => {
+                self.respond(request, Json::object(vec![]));
+                Action::None
+            }
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.frames.borrow().len()
+    }
+
+    fn should_stop(&self, line: usize) -> Option<&'static str> {
+        let depth = self.depth();
+
+        match self.mode.get() {
+            StepMode::Pause => Some("pause"),
+            StepMode::StepIn => Some("step"),
+            StepMode::StepOver(d) if depth <= d => Some("step"),
+            StepMode::StepOut(d) if depth < d => Some("step"),
+            _ if self.has_breakpoint(line) => Some("breakpoint"),
+            _ => None,
+        }
+    }
+
+    fn has_breakpoint(&self, line: usize) -> bool {
+        self.breakpoints
+            .borrow()
+            .get(self.path.borrow().as_str())
+            .is_some_and(|lines| lines.contains(&line))
+    }
+
+    fn stop(&self, reason: &str) -> Result<(), String> {
+        self.event(
+            "stopped",
+            Json::object(vec![
+                ("reason", reason.into()),
+                ("threadId", 1.into()),
+                ("allThreadsStopped", true.into()),
+            ]),
+        );
+
+        loop {
+            let message = self
+                .messages
+                .recv()
+                .map_err(|_| "Debugger disconnected".to_string())?;
+
+            match self.handle(&message) {
+                Action::Resume(mode) => {
+                    self.mode.set(mode);
+                    self.handles.borrow_mut().clear();
+                    return Ok(());
+                }
+                Action::Disconnect => return Err("Debugger disconnected".into()),
+                _ => (),
+            }
+        }
+    }
+
+    fn stack_trace(&self) -> Json {
+        let path = self.path.borrow();
+        let source = Json::object(vec![
+            ("name", path.rsplit('/').next().unwrap_or("").into()),
+            ("path", path.as_str().into()),
+        ]);
+
+        let frames: Vec<Json> = self
+            .frames
+            .borrow()
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(id, frame)| {
+                Json::object(vec![
+                    ("id", id.into()),
+                    ("name", frame.name.as_str().into()),
+                    ("line", frame.line.into()),
+                    ("column", 1.into()),
+                    ("source", source.clone()),
+                ])
+            })
+            .collect();
+
+        Json::object(vec![
+            ("totalFrames", frames.len().into()),
+            ("stackFrames", Json::Array(frames)),
+        ])
+    }
+
+    fn scopes(&self, frame: usize) -> Json {
+        let environment = match self.frames.borrow().get(frame) {
+            Some(Frame {
+                environment: Some(e),
+                ..
+            }) => e.clone(),
+            _ => return Json::object(vec![("scopes", Json::Array(vec![]))]),
+        };
+
+        let mut globals = environment.clone();
+        while let Some(enclosing) = globals.enclosing() {
+            globals = enclosing;
+        }
+
+        let mut scopes = Vec::new();
+        if environment.enclosing().is_some() {
+            scopes.push(self.scope("Locals", Handle::Locals(environment)));
+        }
+        scopes.push(self.scope("Globals", Handle::Globals(globals)));
+
+        Json::object(vec![("scopes", Json::Array(scopes))])
+    }
+
+    fn scope(&self, name: &str, handle: Handle) -> Json {
+        Json::object(vec![
+            ("name", name.into()),
+            ("variablesReference", self.add_handle(handle).into()),
+            ("expensive", false.into()),
+        ])
+    }
+
+    fn add_handle(&self, handle: Handle) -> usize {
+        let mut handles = self.handles.borrow_mut();
+        handles.push(handle);
+        handles.len()
+    }
+
+    fn variables(&self, reference: usize) -> Json {
+        let values = match self.handles.borrow().get(reference.wrapping_sub(1)) {
+            Some(Handle::Locals(environment)) => local_values(environment),
+            Some(Handle::Globals(environment)) => environment.values(),
+            Some(Handle::Instance(instance)) => instance.fields(),
+            None => Vec::new(),
+        };
+
+        let variables = values
+            .into_iter()
+            .map(|(name, value)| {
+                let reference = match &value {
+                    Literal::ClassInstance(i) => self.add_handle(Handle::Instance(i.clone())),
+                    _ => 0,
+                };
+
+                Json::object(vec![
+                    ("name", private::unscoped(&name).into()),
+                    ("value", value.to_string().into()),
+                    ("variablesReference", reference.into()),
+                ])
+            })
+            .collect();
+
+        Json::object(vec![("variables", Json::Array(variables))])
+    }
+}
+
+/// Every variable visible from the environment, excluding globals. Inner
+/// scopes shadow outer ones.
+fn local_values(environment: &Environment) -> Vec<(String, Literal)> {
+    let mut values: Vec<(String, Literal)> = Vec::new();
+    let mut current = environment.clone();
+
+    while let Some(enclosing) = current.enclosing() {
+        for (name, value) in current.values() {
+            if !values.iter().any(|(n, _)| n == &name) {
+                values.push((name, value));
+            }
+        }
+        current = enclosing;
+    }
+
+    values
+}
+
+impl Debugger for Session {
+    fn on_statement(&self, line: usize, environment: &Environment) -> Result<(), String> {
+        let stopped_here = match self.frames.borrow_mut().last_mut() {
+            Some(frame) => {
+                frame.stopped &= frame.line == line;
+                frame.line = line;
+                frame.environment = Some(environment.clone());
+                frame.stopped
+            }
+            None => false,
+        };
+
+        loop {
+            match self.messages.try_recv() {
+                Ok(message) => {
+                    if let Action::Disconnect = self.handle(&message) {
+                        return Err("Debugger disconnected".into());
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Err("Debugger disconnected".into()),
+            }
+        }
+
+        match self.should_stop(line) {
+            Some(reason) if reason == "pause" || !stopped_here => {
+                if let Some(frame) = self.frames.borrow_mut().last_mut() {
+                    frame.stopped = true;
+                }
+                self.stop(reason)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn on_call(&self, name: &str) {
+        self.frames.borrow_mut().push(Frame {
+            name: name.to_string(),
+            line: 0,
+            environment: None,
+            stopped: false,
+        });
+    }
+
+    fn on_return(&self) {
+        self.frames.borrow_mut().pop();
+    }
+}
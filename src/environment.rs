@@ -1,8 +1,11 @@
 use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
-use crate::tokens::Literal;
+use crate::{
+    gc::{self, Trace},
+    tokens::Literal,
+};
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Debug, Eq, Hash, PartialEq)]
 struct Inner {
     enclosing: Option<Environment>,
     values: BTreeMap<String, Literal>,
@@ -13,17 +16,27 @@ pub(crate) struct Environment(Rc<RefCell<Inner>>);
 
 impl Environment {
     pub fn new() -> Environment {
-        Environment(Rc::new(RefCell::new(Inner {
+        Environment::allocate(Inner {
             enclosing: None,
             values: BTreeMap::new(),
-        })))
+        })
     }
 
     pub fn with_enclosing(enclosing: Environment) -> Environment {
-        Environment(Rc::new(RefCell::new(Inner {
+        Environment::allocate(Inner {
             enclosing: Some(enclosing),
             values: BTreeMap::new(),
-        })))
+        })
+    }
+
+    fn allocate(inner: Inner) -> Environment {
+        let inner = Rc::new(RefCell::new(inner));
+        gc::register(Rc::downgrade(&inner) as _);
+        Environment(inner)
+    }
+
+    pub(crate) fn address(&self) -> usize {
+        gc::address(&self.0)
     }
 
     pub fn define(&mut self, name: &str, value: Literal) {
@@ -93,6 +106,19 @@ impl Environment {
         self.0.borrow().values.get(name).cloned()
     }
 
+    pub(crate) fn enclosing(&self) -> Option<Environment> {
+        self.0.borrow().enclosing.clone()
+    }
+
+    pub(crate) fn values(&self) -> Vec<(String, Literal)> {
+        self.0
+            .borrow()
+            .values
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
     pub fn get_global(&self, name: &str) -> Option<Literal> {
         match &self.0.borrow().enclosing {
             Some(e) => e.get_global(name),
@@ -101,6 +127,28 @@ impl Environment {
     }
 }
 
+impl Drop for Inner {
+    fn drop(&mut self) {
+        gc::release();
+    }
+}
+
+impl Trace for Inner {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        if let Some(enclosing) = &self.enclosing {
+            visit(enclosing.address());
+        }
+        for value in self.values.values() {
+            gc::trace_literal(value, visit);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.enclosing = None;
+        self.values.clear();
+    }
+}
+
 impl std::fmt::Debug for Environment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let inner = self.0.borrow();
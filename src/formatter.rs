@@ -0,0 +1,536 @@
+use std::{cell::RefCell, collections::VecDeque};
+
+use crate::{
+    expr::*,
+    parser::{self, Span, Spans},
+    stmt::{FunctionStmt, Stmt},
+    tokens::{Comment, Literal, Tokens},
+};
+
+const INDENT: &str = "  ";
+
+/// Formats Lox source with two space indentation, one statement per line
+/// and opening braces on the line of the statement they belong to. Comments
+/// are kept, and runs of blank lines are collapsed into one.
+pub(crate) fn format(source: &str) -> Result<String, Vec<String>> {
+    let tokens: Tokens = source.parse()?;
+    let comments = tokens.comments().iter().cloned().collect();
+    let (statements, spans) = parser::parse_with_spans(tokens)?;
+
+    let mut formatter = Formatter {
+        spans,
+        comments,
+        out: String::new(),
+        current: String::new(),
+        indent: 0,
+        last_line: 0,
+        closing_line: 0,
+    };
+
+    for statement in statements.iter() {
+        formatter.statement(statement);
+    }
+    formatter.comments_before(usize::MAX);
+
+    Ok(formatter.out)
+}
+
+struct Formatter {
+    spans: Spans,
+    comments: VecDeque<Comment>,
+    out: String,
+    current: String,
+    indent: usize,
+    /// The source line of the last line written, used to keep blank lines.
+    last_line: usize,
+    /// The line the innermost open block closes on. A comment after a block
+    /// written on one line belongs after its closing brace.
+    closing_line: usize,
+}
+
+impl Formatter {
+    fn span(&self, id: usize) -> Span {
+        self.spans.get(id).unwrap_or(Span {
+            start: self.last_line,
+            end: self.last_line,
+        })
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.current.is_empty() {
+            self.current = INDENT.repeat(self.indent);
+        }
+        self.current.push_str(text);
+    }
+
+    /// Ends the current line, attaching any comment that trailed the source
+    /// line it came from.
+    fn end_line(&mut self, source_line: usize) {
+        let closing_line = self.closing_line;
+        let trailing = self
+            .comments
+            .pop_front_if(|c| c.trailing && c.line == source_line && source_line != closing_line);
+
+        if let Some(comment) = trailing {
+            self.current.push(' ');
+            self.current.push_str(&comment.text);
+        }
+
+        self.out.push_str(&self.current);
+        self.out.push('\n');
+        self.current.clear();
+        self.last_line = self.last_line.max(source_line);
+    }
+
+    /// Keeps a blank line the source had before `line`, unless it would open
+    /// a file or a block.
+    fn separate(&mut self, line: usize) {
+        let at_start = self.out.is_empty() || self.out.ends_with("{\n");
+
+        if !at_start && line > self.last_line + 1 {
+            self.out.push('\n');
+        }
+    }
+
+    /// Writes the comments that appear before `line` on lines of their own.
+    fn comments_before(&mut self, line: usize) {
+        while self.comments.front().is_some_and(|c| c.line < line) {
+            let comment = self.comments.pop_front().unwrap();
+            self.separate(comment.line);
+            self.write(&comment.text);
+            self.end_line(comment.line);
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        let span = self.span(statement.id());
+        self.comments_before(span.start);
+        self.separate(span.start);
+        self.inline_statement(statement);
+    }
+
+    /// Writes a statement onto the current line, e.g. after `else `.
+    fn inline_statement(&mut self, statement: &Stmt) {
+        let span = self.span(statement.id());
+
+        match statement {
+            Stmt::Block(s) if self.spans.is_synthetic(s.id) => self.for_statement(&s.statements),
+            Stmt::Block(s) => {
+                self.block(&s.statements, span);
+                self.end_line(span.end);
+            }
+            Stmt::Class(s) => {
+                self.write(&format!("class {}", s.name.lexeme));
+                if let Some(superclass) = &s.superclass {
+                    self.write(&format!(" < {}", superclass.name.lexeme));
+                }
+
+                let names: Vec<&str> = s.traits.iter().map(|t| t.name.lexeme.as_str()).collect();
+                if !names.is_empty() {
+                    self.write(&format!(" with {}", names.join(", ")));
+                }
+
+                // each member with what's written before its name
+                let mut methods: Vec<(&FunctionStmt, &str)> = s
+                    .methods
+                    .iter()
+                    .map(|m| (m, ""))
+                    .chain(s.class_methods.iter().map(|m| (m, "class ")))
+                    .chain(s.getters.iter().map(|m| (m, "")))
+                    .chain(s.setters.iter().map(|m| (m, "set ")))
+                    .collect();
+                // in source order, even for members that share a line
+                methods.sort_by_key(|(m, _)| (m.name.line_number, m.name.column));
+
+                self.members(methods, &s.getters, span);
+                self.end_line(span.end);
+            }
+            Stmt::Enum(s) => {
+                let variants: Vec<&str> = s.variants.iter().map(|v| v.lexeme.as_str()).collect();
+                match variants.is_empty() {
+                    true => self.write(&format!("enum {} {{}}", s.name.lexeme)),
+                    false => self.write(&format!(
+                        "enum {} {{ {} }}",
+                        s.name.lexeme,
+                        variants.join(", ")
+                    )),
+                }
+                self.end_line(span.end);
+            }
+            Stmt::Expression(s) => {
+                let expression = self.expression(&s.expression);
+                self.write(&format!("{};", expression));
+                self.end_line(span.end);
+            }
+            Stmt::Function(s) => {
+                self.write("fun ");
+                self.function(s);
+            }
+            Stmt::If(s) => {
+                let has_else = !self.spans.is_synthetic(s.else_branch.id());
+
+                let condition = self.expression(&s.condition);
+                self.write(&format!("if ({}) ", condition));
+                self.branch(&s.then_branch, has_else);
+
+                if has_else {
+                    match self.current.is_empty() {
+                        true => self.write("else "),
+                        false => self.write(" else "),
+                    }
+                    self.branch(&s.else_branch, false);
+                }
+            }
+            Stmt::Match(s) => {
+                let closing_line = std::mem::replace(&mut self.closing_line, span.end);
+                let value = self.expression(&s.value);
+                self.write(&format!("match ({}) {{", value));
+                self.end_line(span.start);
+                self.indent += 1;
+                for (i, arm) in s.arms.iter().enumerate() {
+                    let arm_span = self.span(arm.id());
+                    self.comments_before(arm_span.start);
+                    self.separate(arm_span.start);
+                    match s.patterns.get(i) {
+                        Some(pattern) => {
+                            let pattern = self.expressions().visit_get(pattern);
+                            self.write(&format!("{} => ", pattern))
+                        }
+                        None => self.write("else => "),
+                    }
+                    self.branch(arm, false);
+                }
+                self.comments_before(span.end);
+                self.indent -= 1;
+                self.closing_line = closing_line;
+                self.write("}");
+                self.end_line(span.end);
+            }
+            Stmt::Print(s) => {
+                let expression = self.expression(&s.expression);
+                self.write(&format!("print {};", expression));
+                self.end_line(span.end);
+            }
+            Stmt::Return(s) => {
+                match self.spans.is_synthetic(s.value.id()) {
+                    true => self.write("return;"),
+                    false => {
+                        let value = self.expression(&s.value);
+                        self.write(&format!("return {};", value))
+                    }
+                }
+                self.end_line(span.end);
+            }
+            Stmt::Trait(s) => {
+                self.write(&format!("trait {}", s.name.lexeme));
+                self.members(s.methods.iter().map(|m| (m, "")).collect(), &[], span);
+                self.end_line(span.end);
+            }
+            Stmt::Var(_) => {
+                let declaration = self.declaration(statement);
+                self.write(&declaration);
+                self.end_line(span.end);
+            }
+            Stmt::While(s) => {
+                let condition = self.expression(&s.condition);
+                self.write(&format!("while ({}) ", condition));
+                self.branch(&s.body, false);
+            }
+        }
+    }
+
+    /// Writes the body of an `if`, `else`, `while` or `for`. When an `else`
+    /// follows, a block body is left open so it goes after the closing brace.
+    fn branch(&mut self, statement: &Stmt, has_else: bool) {
+        match statement {
+            Stmt::Block(s) if !self.spans.is_synthetic(s.id) => {
+                let span = self.span(s.id);
+                self.block(&s.statements, span);
+
+                if !has_else {
+                    self.end_line(span.end);
+                }
+            }
+            _ => self.inline_statement(statement),
+        }
+    }
+
+    /// Turns the block a `for` loop desugars to back into the loop.
+    fn for_statement(&mut self, statements: &[Stmt]) {
+        let (initializer, (condition, body)) = match statements {
+            [initializer, Stmt::While(w)] => (initializer, (&w.condition, &w.body)),
+            _ => unreachable!("for loops desugar to an initializer and a while"),
+        };
+        let (body, increment) = match body.as_ref() {
+            Stmt::Block(b) => match b.statements.as_slice() {
+                [body, Stmt::Expression(increment)] => (body, &increment.expression),
+                _ => unreachable!("for loop bodies end with their increment"),
+            },
+            _ => unreachable!("for loop bodies are blocks"),
+        };
+
+        let initializer = match self.spans.is_synthetic(initializer.id()) {
+            true => String::new(),
+            false => self.declaration(initializer),
+        };
+        let condition = match self.spans.is_synthetic(condition.id()) {
+            true => String::new(),
+            false => format!(" {}", self.expression(condition)),
+        };
+        let increment = match self.spans.is_synthetic(increment.id()) {
+            true => String::new(),
+            false => format!(" {}", self.expression(increment)),
+        };
+
+        self.write(&format!(
+            "for ({};{};{}) ",
+            initializer.trim_end_matches(';'),
+            condition,
+            increment
+        ));
+        self.branch(body, false);
+    }
+
+    /// A `var` or expression statement, as it's written in a `for` clause.
+    fn declaration(&mut self, statement: &Stmt) -> String {
+        match statement {
+            Stmt::Var(s) if self.spans.is_synthetic(s.initializer.id()) => {
+                format!("var {};", s.name.lexeme)
+            }
+            Stmt::Var(s) => format!(
+                "var {} = {};",
+                s.name.lexeme,
+                self.expression(&s.initializer)
+            ),
+            Stmt::Expression(s) => format!("{};", self.expression(&s.expression)),
+            _ => unreachable!("only declarations and expressions initialize a for loop"),
+        }
+    }
+
+    fn function(&mut self, function: &FunctionStmt) {
+        let span = self.span(function.id);
+        let params: Vec<&str> = function.params.iter().map(|p| p.lexeme.as_str()).collect();
+
+        self.write(&format!("{}({}) ", function.name.lexeme, params.join(", ")));
+        self.block(&function.body, span);
+        self.end_line(span.end);
+    }
+
+    /// Writes the braced body of a class or trait, leaving the closing brace
+    /// on the current line.
+    fn members(
+        &mut self,
+        methods: Vec<(&FunctionStmt, &str)>,
+        getters: &[FunctionStmt],
+        span: Span,
+    ) {
+        if methods.is_empty() && !self.has_comments_before(span.end) {
+            self.write(" {}");
+            return;
+        }
+
+        let closing_line = std::mem::replace(&mut self.closing_line, span.end);
+        self.write(" {");
+        self.end_line(span.start);
+        self.indent += 1;
+        for (method, prefix) in methods {
+            let method_span = self.span(method.id);
+            self.comments_before(method_span.start);
+            self.separate(method_span.start);
+            self.write(prefix);
+            match getters.iter().any(|g| g.id == method.id) {
+                true => self.getter(method),
+                false => self.function(method),
+            }
+        }
+        self.comments_before(span.end);
+        self.indent -= 1;
+        self.closing_line = closing_line;
+        self.write("}");
+    }
+
+    fn getter(&mut self, getter: &FunctionStmt) {
+        let span = self.span(getter.id);
+
+        self.write(&format!("{} ", getter.name.lexeme));
+        self.block(&getter.body, span);
+        self.end_line(span.end);
+    }
+
+    /// Writes a braced list of statements, leaving the closing brace on the
+    /// current line.
+    fn block(&mut self, statements: &[Stmt], span: Span) {
+        if statements.is_empty() && !self.has_comments_before(span.end) {
+            self.write("{}");
+            return;
+        }
+
+        let closing_line = std::mem::replace(&mut self.closing_line, span.end);
+        self.write("{");
+        self.end_line(span.start);
+        self.indent += 1;
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.comments_before(span.end);
+        self.indent -= 1;
+        self.closing_line = closing_line;
+        self.write("}");
+    }
+
+    fn has_comments_before(&self, line: usize) -> bool {
+        self.comments.front().is_some_and(|c| c.line < line)
+    }
+
+    fn expression(&mut self, expr: &Expr) -> String {
+        self.expressions().expression(expr)
+    }
+
+    /// Formats expressions on the current line, with continuation lines
+    /// indented one level further.
+    fn expressions(&mut self) -> ExpressionFormatter<'_> {
+        ExpressionFormatter {
+            spans: &self.spans,
+            comments: RefCell::new(&mut self.comments),
+            indent: INDENT.repeat(self.indent + 1),
+        }
+    }
+}
+
+/// Writes an expression on one line, except that a comment inside it ends
+/// the line it's on, so comments stay where they were among the operands.
+struct ExpressionFormatter<'a> {
+    spans: &'a Spans,
+    comments: RefCell<&'a mut VecDeque<Comment>>,
+    indent: String,
+}
+
+impl ExpressionFormatter<'_> {
+    fn expression(&self, expr: &Expr) -> String {
+        // only primary expressions are recorded at the line they start on
+        let primary = matches!(
+            expr,
+            Expr::Grouping(_)
+                | Expr::List(_)
+                | Expr::Literal(_)
+                | Expr::Map(_)
+                | Expr::Super(_)
+                | Expr::This(_)
+                | Expr::Variable(_)
+        );
+
+        let mut text = String::new();
+        if let Some(span) = self.spans.get(expr.id()).filter(|_| primary) {
+            let mut comments = self.comments.borrow_mut();
+            while let Some(comment) = comments.pop_front_if(|c| c.line < span.start) {
+                text.push_str(&comment.text);
+                text.push('\n');
+                text.push_str(&self.indent);
+            }
+        }
+
+        text + &walk_expr(self, expr)
+    }
+}
+
+impl Visitor<String> for ExpressionFormatter<'_> {
+    fn visit_assign(&self, expr: &AssignExpr) -> String {
+        format!("{} = {}", expr.name.lexeme, self.expression(&expr.value))
+    }
+
+    fn visit_binary(&self, expr: &BinaryExpr) -> String {
+        format!(
+            "{} {} {}",
+            self.expression(&expr.left),
+            expr.operator.lexeme,
+            self.expression(&expr.right)
+        )
+    }
+
+    fn visit_call(&self, expr: &CallExpr) -> String {
+        let callee = self.expression(&expr.callee);
+        let arguments: Vec<String> = expr.arguments.iter().map(|e| self.expression(e)).collect();
+        format!("{}({})", callee, arguments.join(", "))
+    }
+
+    fn visit_get(&self, expr: &GetExpr) -> String {
+        format!("{}.{}", self.expression(&expr.object), expr.name.lexeme)
+    }
+
+    fn visit_grouping(&self, expr: &GroupingExpr) -> String {
+        format!("({})", self.expression(&expr.expression))
+    }
+
+    fn visit_index(&self, expr: &IndexExpr) -> String {
+        format!(
+            "{}[{}]",
+            self.expression(&expr.object),
+            self.expression(&expr.index)
+        )
+    }
+
+    fn visit_index_set(&self, expr: &IndexSetExpr) -> String {
+        format!(
+            "{}[{}] = {}",
+            self.expression(&expr.object),
+            self.expression(&expr.index),
+            self.expression(&expr.value)
+        )
+    }
+
+    fn visit_list(&self, expr: &ListExpr) -> String {
+        let elements: Vec<String> = expr.elements.iter().map(|e| self.expression(e)).collect();
+        format!("[{}]", elements.join(", "))
+    }
+
+    fn visit_map(&self, expr: &MapExpr) -> String {
+        let entries: Vec<String> = expr
+            .keys
+            .iter()
+            .zip(expr.values.iter())
+            .map(|(k, v)| format!("{}: {}", self.expression(k), self.expression(v)))
+            .collect();
+        format!("{{{}}}", entries.join(", "))
+    }
+
+    fn visit_literal(&self, expr: &LiteralExpr) -> String {
+        match &expr.value {
+            Literal::String(s) => format!("\"{}\"", s),
+            value => value.to_string(),
+        }
+    }
+
+    fn visit_logical(&self, expr: &LogicalExpr) -> String {
+        format!(
+            "{} {} {}",
+            self.expression(&expr.left),
+            expr.operator.lexeme,
+            self.expression(&expr.right)
+        )
+    }
+
+    fn visit_set(&self, expr: &SetExpr) -> String {
+        format!(
+            "{}.{} = {}",
+            self.expression(&expr.object),
+            expr.name.lexeme,
+            self.expression(&expr.value)
+        )
+    }
+
+    fn visit_super(&self, expr: &SuperExpr) -> String {
+        format!("super.{}", expr.method.lexeme)
+    }
+
+    fn visit_this(&self, _expr: &ThisExpr) -> String {
+        "this".to_string()
+    }
+
+    fn visit_unary(&self, expr: &UnaryExpr) -> String {
+        format!("{}{}", expr.operator.lexeme, self.expression(&expr.right))
+    }
+
+    fn visit_variable(&self, expr: &VariableExpr) -> String {
+        expr.name.lexeme.to_string()
+    }
+}
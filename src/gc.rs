@@ -0,0 +1,191 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use crate::tokens::{Callable, Class, Literal};
+
+/// Allocations between automatic collections, before the heap has grown.
+const MIN_COLLECTION_THRESHOLD: usize = 1024;
+
+/// Environments, instances, lists and maps are reference counted, which frees them
+/// as soon as nothing points at them, except when they point at each other: a
+/// closure and the environment it's defined in, or two instances holding each
+/// other in fields. The heap keeps a weak reference to every one of them so it can find
+/// those cycles and break them.
+///
+/// It doesn't need to know the roots. Any object with more strong references
+/// than the heap can account for from other objects is held by the interpreter
+/// itself, so it and everything it reaches is live. Whatever is left is only
+/// kept alive by cycles and has its references cleared.
+pub(crate) trait Trace {
+    /// Calls `visit` with the address of every object this one refers to.
+    fn trace(&self, visit: &mut dyn FnMut(usize));
+
+    /// Drops every reference this object holds.
+    fn clear(&mut self);
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Stats {
+    pub allocated: usize,
+    pub collections: usize,
+    pub freed: usize,
+    pub live: usize,
+}
+
+struct Heap {
+    objects: Vec<Weak<RefCell<dyn Trace>>>,
+    stress: bool,
+    next_collection: usize,
+    stats: Stats,
+}
+
+thread_local! {
+    /// Objects allocated and not yet dropped, kept apart from the heap because
+    /// objects are dropped while it's collecting.
+    static LIVE: Cell<usize> = const { Cell::new(0) };
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        objects: Vec::new(),
+        stress: false,
+        next_collection: MIN_COLLECTION_THRESHOLD,
+        stats: Stats::default(),
+    });
+}
+
+/// Tracks a newly allocated object, collecting first if it's time to.
+pub(crate) fn register(object: Weak<RefCell<dyn Trace>>) {
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+
+        if heap.stress || heap.objects.len() >= heap.next_collection {
+            heap.collect();
+        }
+
+        heap.objects.push(object);
+        heap.stats.allocated += 1;
+    });
+    LIVE.with(|live| live.set(live.get() + 1));
+}
+
+/// Called when a registered object is dropped.
+pub(crate) fn release() {
+    // objects can outlive the counter when the thread exits
+    let _ = LIVE.try_with(|live| live.set(live.get() - 1));
+}
+
+/// How many objects are allocated right now.
+pub(crate) fn live() -> usize {
+    LIVE.with(Cell::get)
+}
+
+/// Collects on every allocation, to shake out objects that are used without
+/// being reachable from anything the collector can see.
+pub(crate) fn set_stress(stress: bool) {
+    HEAP.with(|heap| heap.borrow_mut().stress = stress)
+}
+
+/// Frees every object only kept alive by cycles and returns how many there were.
+pub(crate) fn collect() -> usize {
+    HEAP.with(|heap| heap.borrow_mut().collect())
+}
+
+pub(crate) fn stats() -> Stats {
+    HEAP.with(|heap| Stats {
+        live: live(),
+        ..heap.borrow().stats
+    })
+}
+
+pub(crate) fn address<T>(object: &Rc<RefCell<T>>) -> usize {
+    Rc::as_ptr(object) as *const () as usize
+}
+
+pub(crate) fn trace_literal(value: &Literal, visit: &mut dyn FnMut(usize)) {
+    match value {
+        Literal::Callable(c) => match &c.callable {
+            Callable::Class(class) | Callable::Trait(class) => trace_class(class, visit),
+            Callable::Function(f) => visit(f.env.address()),
+            Callable::Native(n) => {
+                if let Some(this) = &n.this {
+                    trace_literal(this, visit);
+                }
+            }
+        },
+        Literal::ClassInstance(instance) => visit(instance.address()),
+        Literal::List(list) => visit(list.address()),
+        Literal::Map(map) => visit(map.address()),
+        _ => {}
+    }
+}
+
+pub(crate) fn trace_class(class: &Class, visit: &mut dyn FnMut(usize)) {
+    for method in class.methods.values().chain(class.setters.values()) {
+        visit(method.env.address());
+    }
+    for t in class.traits.iter() {
+        trace_class(t, visit);
+    }
+    if let Some(superclass) = &class.superclass {
+        visit(superclass.address());
+    }
+    if let Some(statics) = &class.statics {
+        visit(statics.address());
+    }
+}
+
+impl Heap {
+    fn collect(&mut self) -> usize {
+        let objects: Vec<Rc<RefCell<dyn Trace>>> =
+            self.objects.iter().filter_map(Weak::upgrade).collect();
+        let index: HashMap<usize, usize> = objects
+            .iter()
+            .enumerate()
+            .map(|(i, o)| (Rc::as_ptr(o) as *const () as usize, i))
+            .collect();
+
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); objects.len()];
+        let mut internal = vec![0; objects.len()];
+
+        for (i, object) in objects.iter().enumerate() {
+            // an object being changed right now is in use, and whatever it
+            // refers to is treated as held from outside the heap
+            if let Ok(object) = object.try_borrow() {
+                object.trace(&mut |address| {
+                    if let Some(&j) = index.get(&address) {
+                        edges[i].push(j);
+                        internal[j] += 1;
+                    }
+                });
+            }
+        }
+
+        // one of the strong references is the one in `objects`
+        let mut live = vec![false; objects.len()];
+        let mut pending: Vec<usize> = (0..objects.len())
+            .filter(|&i| Rc::strong_count(&objects[i]) - 1 > internal[i])
+            .collect();
+
+        while let Some(i) = pending.pop() {
+            if !live[i] {
+                live[i] = true;
+                pending.extend(edges[i].iter().filter(|&&j| !live[j]));
+            }
+        }
+
+        let mut freed = 0;
+        for (object, _) in objects.iter().zip(&live).filter(|(_, live)| !**live) {
+            object.borrow_mut().clear();
+            freed += 1;
+        }
+
+        drop(objects);
+        self.objects.retain(|o| o.strong_count() > 0);
+        self.next_collection = MIN_COLLECTION_THRESHOLD.max(self.objects.len() * 2);
+        self.stats.collections += 1;
+        self.stats.freed += freed;
+
+        freed
+    }
+}
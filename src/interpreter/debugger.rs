@@ -0,0 +1,14 @@
+use crate::environment::Environment;
+
+/// Hooks the interpreter calls while running under a debugger.
+pub(crate) trait Debugger {
+    /// Called before every statement is executed. Returning an error aborts
+    /// the program.
+    fn on_statement(&self, line: usize, environment: &Environment) -> Result<(), String>;
+
+    /// Called when a Lox function is entered.
+    fn on_call(&self, name: &str);
+
+    /// Called when a Lox function returns, normally or with an error.
+    fn on_return(&self);
+}
@@ -86,6 +86,9 @@ impl Environments {
             (Literal::ClassInstance(_), _) => {
                 Err("Could not resolve 'this' when looking up superclass".into())
             }
+            (Literal::Nil, _) => {
+                Err("Can't use 'super' in a trait method of a class with no superclass.".into())
+            }
             _ => Err("Could not resolve 'super' when looking up superclass".into()),
         }
     }
@@ -0,0 +1,36 @@
+use std::{fmt::Display, time::Duration};
+
+/// A limit from `Options` that a script ran into. Each one stops the script,
+/// and unlike other runtime errors a host can tell them apart.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum LimitExceeded {
+    /// More statements were executed than the step budget allows.
+    Steps(u64),
+    Timeout(Duration),
+    /// More environments, instances, lists and maps were live than allowed.
+    Heap(usize),
+    StringLength(usize),
+    /// Calls nested too deep, with the backtrace of the calls in progress.
+    CallDepth(String),
+    /// A native function that isn't on the allow-list was called.
+    Native(String),
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitExceeded::Steps(steps) => write!(f, "Step budget of {} exceeded.", steps),
+            LimitExceeded::Timeout(timeout) => {
+                write!(f, "Timed out after {}ms.", timeout.as_millis())
+            }
+            LimitExceeded::Heap(objects) => {
+                write!(f, "Heap limit of {} objects exceeded.", objects)
+            }
+            LimitExceeded::StringLength(length) => {
+                write!(f, "String longer than {} characters.", length)
+            }
+            LimitExceeded::CallDepth(backtrace) => f.write_str(backtrace),
+            LimitExceeded::Native(name) => write!(f, "Native function '{}' is not allowed.", name),
+        }
+    }
+}
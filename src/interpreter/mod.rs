@@ -1,26 +1,125 @@
+mod debugger;
 mod environments;
+mod limits;
 
+use std::cell::{Cell, RefCell};
 use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use crate::environment::Environment;
 use crate::expr::*;
-use crate::resolver::Locals;
+use crate::gc;
+use crate::native::{self, Context, NativeError, Streams, VirtualClock};
+use crate::parser::Spans;
+use crate::resolver::{private, Locals};
 use crate::stmt::*;
-use crate::tokens::{Callable, Class, Function, LoxCallable, LoxInstance, TokenType};
+use crate::tokens::{
+    Callable, Class, ClassKind, Function, LoxCallable, LoxInstance, LoxList, LoxMap, TokenType,
+};
 use crate::{expr, tokens::Literal};
 
+pub(crate) use debugger::Debugger;
 use environments::Environments;
+pub(crate) use limits::LimitExceeded;
 
 use Literal as L;
 use TokenType as TT;
 
-use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use rust_decimal::Decimal;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum Error {
     ReturnValue(Literal),
     SingleError(String),
+    /// `return f(...)`: the function to call in place of the one returning,
+    /// its arguments and the line of the call.
+    TailCall(LoxCallable, Vec<Literal>, usize),
+    Limit(LimitExceeded),
+    Exit(i32),
+}
+
+/// Why a script stopped early.
+#[derive(Debug)]
+pub(crate) enum RuntimeError {
+    Errors(Vec<String>),
+    Limit(LimitExceeded),
+    /// The script called `exit()` with this status.
+    Exit(i32),
+}
+
+impl From<Vec<String>> for RuntimeError {
+    fn from(errors: Vec<String>) -> Self {
+        RuntimeError::Errors(errors)
+    }
+}
+
+impl From<RuntimeError> for Vec<String> {
+    fn from(error: RuntimeError) -> Self {
+        match error {
+            RuntimeError::Errors(errors) => errors,
+            RuntimeError::Limit(limit) => prepend_interpreter_error(vec![limit.to_string()]),
+            RuntimeError::Exit(status) => vec![format!("Exited with status {}.", status)],
+        }
+    }
+}
+
+pub(crate) const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// How many frames of a backtrace are printed before the rest are elided.
+const BACKTRACE_FRAMES: usize = 20;
+
+/// How the interpreter runs a script. Limits that are `None` don't apply.
+#[derive(Clone)]
+pub(crate) struct Options {
+    /// Calls nested deeper than this raise a "Stack overflow." runtime error.
+    pub max_call_depth: usize,
+    /// Run the cycle collector on every allocation.
+    pub gc_stress: bool,
+    /// How many statements the script may execute.
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+    /// How many environments, instances, lists and maps may be live at once,
+    /// whatever their size.
+    pub max_heap: Option<usize>,
+    pub max_string_length: Option<usize>,
+    /// The native functions the script may call.
+    pub allowed_natives: Option<Vec<String>>,
+    /// Whether scripts can use the file system natives.
+    pub allow_fs: bool,
+    /// What `args()` returns.
+    pub args: Vec<String>,
+    /// Seeds the random natives, which are seeded from the clock without it.
+    pub seed: Option<u64>,
+    /// Makes `clock()` read virtual time instead of the system clock.
+    pub virtual_clock: Option<VirtualClock>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            gc_stress: false,
+            max_steps: None,
+            timeout: None,
+            max_heap: None,
+            max_string_length: None,
+            allowed_natives: None,
+            allow_fs: true,
+            args: Vec::new(),
+            seed: None,
+            virtual_clock: None,
+        }
+    }
+}
+
+/// A Lox function call in progress.
+struct Frame {
+    name: String,
+    /// The line of the call, in the caller.
+    line: usize,
 }
 
 use Error::ReturnValue;
@@ -41,11 +140,27 @@ impl From<&str> for Error {
 pub(crate) fn interpret(
     globals: Environment,
     locals: Locals,
+    spans: Spans,
+    statements: &Vec<Stmt>,
+    options: Options,
+    streams: Streams,
+) -> Result<(), RuntimeError> {
+    Interpreter::new(globals, locals, spans, options, streams).interpret(statements)
+}
+
+pub(crate) fn debug(
+    globals: Environment,
+    locals: Locals,
+    spans: Spans,
     statements: &Vec<Stmt>,
-) -> Result<(), Vec<String>> {
-    Interpreter::new(globals, locals)
-        .interpret(statements)
-        .map_err(prepend_interpreter_error)
+    options: Options,
+    debugger: Rc<dyn Debugger>,
+    streams: Streams,
+) -> Result<(), RuntimeError> {
+    let mut interpreter = Interpreter::new(globals, locals, spans, options, streams);
+    interpreter.debugger = Some(debugger);
+
+    interpreter.interpret(statements)
 }
 
 fn prepend_interpreter_error(errors: Vec<String>) -> Vec<String> {
@@ -57,24 +172,67 @@ fn prepend_interpreter_error(errors: Vec<String>) -> Vec<String> {
 
 struct Interpreter {
     environments: Environments,
+    spans: Spans,
+    options: Options,
+    frames: RefCell<Vec<Frame>>,
+    debugger: Option<Rc<dyn Debugger>>,
+    steps: Cell<u64>,
+    started: Instant,
+    context: Context,
+    /// The addresses of the lists, maps and instances `print` is writing, so
+    /// that a value that contains itself is written as `[...]` or `{...}`
+    /// instead of forever, and an instance printed by its own `toString()`
+    /// is written without calling it again.
+    printing: RefCell<Vec<usize>>,
 }
 
 impl Interpreter {
-    fn new(globals: Environment, locals: Locals) -> Interpreter {
+    fn new(
+        globals: Environment,
+        locals: Locals,
+        spans: Spans,
+        options: Options,
+        streams: Streams,
+    ) -> Interpreter {
+        gc::set_stress(options.gc_stress);
+
+        let started = Instant::now();
+        let context = Context {
+            deadline: options.timeout.map(|timeout| started + timeout),
+            ..Context::new(
+                streams,
+                options.args.clone(),
+                options.seed,
+                options.virtual_clock.clone(),
+            )
+        };
+
         Interpreter {
             environments: Environments::new(globals, locals),
+            spans,
+            context,
+            options,
+            frames: RefCell::new(Vec::new()),
+            debugger: None,
+            steps: Cell::new(0),
+            started,
+            printing: RefCell::new(Vec::new()),
         }
     }
 
-    fn interpret(&self, statements: &Vec<Stmt>) -> Result<(), Vec<String>> {
+    fn interpret(&self, statements: &Vec<Stmt>) -> Result<(), RuntimeError> {
         for statement in statements.iter() {
             match self.execute(statement) {
                 Ok(_) => (),
                 Err(e) => {
-                    return match e {
-                        ReturnValue(v) => Err(vec![format!("Unexpected return value: {}", v)]),
-                        SingleError(e) => Err(vec![e]),
-                    }
+                    let error = match e {
+                        ReturnValue(v) => format!("Unexpected return value: {}", v),
+                        Error::TailCall(c, _, _) => format!("Unexpected return value: {}", c),
+                        SingleError(e) => e,
+                        Error::Limit(limit) => return Err(RuntimeError::Limit(limit)),
+                        Error::Exit(status) => return Err(RuntimeError::Exit(status)),
+                    };
+                    return Err(RuntimeError::Errors(prepend_interpreter_error(vec![error])));
                 }
             }
         }
@@ -82,7 +240,54 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Charges a statement against the step budget and checks the limits that
+    /// grow while a script runs.
+    fn check_limits(&self) -> Result<(), Error> {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+        self.context.step();
+
+        if let Some(max_steps) = self.options.max_steps.filter(|max| steps > *max) {
+            return Err(Error::Limit(LimitExceeded::Steps(max_steps)));
+        }
+        if let Some(timeout) = self.options.timeout {
+            if self.started.elapsed() > timeout {
+                return Err(Error::Limit(LimitExceeded::Timeout(timeout)));
+            }
+        }
+        if let Some(max_heap) = self.options.max_heap {
+            // cycles waiting to be collected don't count
+            if gc::live() > max_heap && (gc::collect() == 0 || gc::live() > max_heap) {
+                return Err(Error::Limit(LimitExceeded::Heap(max_heap)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks `value` against the string length limit, along with every
+    /// string in the lists and maps inside it.
+    fn check_string(&self, value: Literal) -> Result<Literal, Error> {
+        match self.options.max_string_length {
+            Some(max) if too_long(&value, max, &mut Vec::new()) => {
+                Err(Error::Limit(LimitExceeded::StringLength(max)))
+            }
+            _ => Ok(value),
+        }
+    }
+
     fn execute(&self, statement: &Stmt) -> Result<(), Error> {
+        if let Some(debugger) = &self.debugger {
+            // blocks only group other statements, so there is nothing to stop on
+            if let (Some(span), false) = (
+                self.spans.get(statement.id()),
+                matches!(statement, Stmt::Block(_)),
+            ) {
+                debugger.on_statement(span.start, &self.environments.peek())?;
+            }
+        }
+
+        self.check_limits()?;
         walk_stmt(self, statement)
     }
 
@@ -98,51 +303,276 @@ impl Interpreter {
         Ok(())
     }
 
-    fn call(&self, callable: LoxCallable, arguments: Vec<Literal>) -> Result<Literal, Error> {
-        if callable.arity() != arguments.len() {
-            return Err(SingleError(format!(
-                "Expected {} arguments but got {}.",
-                callable.arity(),
-                arguments.len()
-            )));
-        }
+    fn call(
+        &self,
+        callable: LoxCallable,
+        arguments: Vec<Literal>,
+        line: usize,
+    ) -> Result<Literal, Error> {
+        let (mut callable, mut arguments, mut line) = (callable, arguments, line);
 
-        match &callable.callable {
-            Callable::Class(c) => {
-                let instance = LoxInstance::new(c.clone());
-                if let Some(initializer) = instance.find_method("init") {
-                    let function = initializer.bind(instance.clone());
-                    self.call(
-                        LoxCallable::new("init".to_string(), Callable::Function(function)),
-                        arguments,
-                    )?;
+        // tail calls loop here instead of recursing, so they run in constant
+        // stack and don't count towards the call depth
+        loop {
+            let (arity, max_arity) = (callable.arity(), callable.max_arity());
+            if !(arity..=max_arity).contains(&arguments.len()) {
+                let expected = match arity == max_arity {
+                    true => arity.to_string(),
+                    false => format!("{} to {}", arity, max_arity),
+                };
+                return Err(SingleError(format!(
+                    "Expected {} arguments but got {}.",
+                    expected,
+                    arguments.len()
+                )));
+            }
+
+            let f = match &callable.callable {
+                Callable::Class(c) => {
+                    let instance = LoxInstance::new(c.as_ref().clone());
+                    if let Some(initializer) = instance.find_method("init") {
+                        let function = initializer.bind(instance.clone());
+                        self.call(
+                            LoxCallable::new("init".to_string(), Callable::Function(function)),
+                            arguments,
+                            line,
+                        )?;
+                    } else if let Some(initializer) = instance.find_native("init") {
+                        self.call(
+                            LoxCallable::new(
+                                format!("{}.init", c.name),
+                                Callable::Native(initializer),
+                            ),
+                            arguments,
+                            line,
+                        )?;
+                    }
+                    return Ok(Literal::ClassInstance(instance));
+                }
+                Callable::Native(n) => {
+                    let listed = match &self.options.allowed_natives {
+                        Some(allowed) => allowed.contains(&callable.name),
+                        None => true,
+                    };
+                    let fs = native::fs::NATIVES.contains(&callable.name.as_str());
+                    // enums and built-in types are part of the language, so
+                    // their natives are always allowed
+                    let builtin = matches!(
+                        n.this.as_deref(),
+                        Some(L::ClassInstance(this))
+                            if matches!(this.class_kind(), ClassKind::Enum | ClassKind::BuiltInType)
+                    );
+                    let allowed = builtin || (listed && (self.options.allow_fs || !fs));
+                    if let Some(this) = &n.this {
+                        arguments.insert(0, this.as_ref().clone());
+                    }
+
+                    return match allowed {
+                        true => match (n.function)(&self.context, arguments) {
+                            Ok(value) => self.check_string(value),
+                            Err(NativeError::Error(e)) => Err(SingleError(e)),
+                            Err(NativeError::Exit(status)) => Err(Error::Exit(status)),
+                        },
+                        false => Err(Error::Limit(LimitExceeded::Native(callable.name))),
+                    };
                 }
-                Ok(Literal::ClassInstance(instance))
+                Callable::Function(f) => f,
+                Callable::Trait(_) => {
+                    return Err(SingleError(format!("Can't call trait {}.", callable.name)))
+                }
+            };
+
+            if self.frames.borrow().len() >= self.options.max_call_depth {
+                return Err(Error::Limit(LimitExceeded::CallDepth(
+                    self.stack_overflow(line),
+                )));
             }
-            Callable::Function(f) => {
-                let mut env = Environment::with_enclosing(f.env.clone());
 
-                for (param, arg) in f.params.iter().zip(arguments) {
-                    env.define(&param.lexeme, arg);
+            let mut env = Environment::with_enclosing(f.env.clone());
+
+            for (param, arg) in f.params.iter().zip(arguments) {
+                env.define(&param.lexeme, arg);
+            }
+
+            if let Some(debugger) = &self.debugger {
+                debugger.on_call(&callable.name);
+            }
+
+            self.frames.borrow_mut().push(Frame {
+                name: callable.name.clone(),
+                line,
+            });
+            self.environments.push_scope(env);
+
+            let result = match self.execute_block(&f.body) {
+                Ok(_) if f.is_initializer => {
+                    Ok(self.environments.get_at_distance(0, "this").unwrap())
                 }
+                Ok(_) => Ok(Literal::Nil),
+                Err(e) => match e {
+                    ReturnValue(value) => Ok(value),
+                    e => Err(e),
+                },
+            };
 
-                self.environments.push_scope(env);
+            self.environments.pop_scope();
+            self.frames.borrow_mut().pop();
 
-                let result = match self.execute_block(&f.body) {
-                    Ok(_) if f.is_initializer => {
-                        Ok(self.environments.get_at_distance(0, "this").unwrap())
-                    }
-                    Ok(_) => Ok(Literal::Nil),
-                    Err(e) => match e {
-                        ReturnValue(value) => Ok(value),
-                        e => Err(e),
-                    },
-                };
+            if let Some(debugger) = &self.debugger {
+                debugger.on_return();
+            }
 
-                self.environments.pop_scope();
-                result
+            match result {
+                Err(Error::TailCall(next, next_arguments, next_line)) => {
+                    callable = next;
+                    arguments = next_arguments;
+                    line = next_line;
+                }
+                result => return result,
             }
-            Callable::Native(n) => Ok(n()),
+        }
+    }
+
+    /// The "Stack overflow." error for a call on `line`, with a backtrace of
+    /// the calls in progress, innermost first.
+    fn stack_overflow(&self, line: usize) -> String {
+        let frames = self.frames.borrow();
+        let mut message = String::from("Stack overflow.");
+
+        // each frame's call happened in the frame below it
+        let callers = frames
+            .iter()
+            .rev()
+            .map(|f| format!("{}()", f.name))
+            .chain(std::iter::once("script".to_string()));
+        let lines = std::iter::once(line).chain(frames.iter().rev().map(|f| f.line));
+        let trace: Vec<String> = lines
+            .zip(callers)
+            .map(|(line, caller)| format!("[line {}] in {}", line, caller))
+            .collect();
+
+        for entry in trace.iter().take(BACKTRACE_FRAMES) {
+            message.push('\n');
+            message.push_str(entry);
+        }
+        if trace.len() > BACKTRACE_FRAMES + 1 {
+            message.push_str(&format!(
+                "\n... {} more calls",
+                trace.len() - BACKTRACE_FRAMES - 1
+            ));
+        }
+        if trace.len() > BACKTRACE_FRAMES {
+            message.push('\n');
+            message.push_str(trace.last().unwrap());
+        }
+
+        message
+    }
+
+    /// Calls a method an instance can hook into the language with, like an
+    /// operator overload or `toString()`, or gives `None` if it doesn't have
+    /// one.
+    fn call_hook(
+        &self,
+        instance: &LoxInstance,
+        method: &str,
+        arguments: Vec<Literal>,
+        line: usize,
+    ) -> Result<Option<Literal>, Error> {
+        match instance.method(method) {
+            Some(callable) => self.call(callable, arguments, line).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The key already in `map` that `key` equals, if it's an instance with an
+    /// `equals()` method, so equal instances find the same entry. Only keys
+    /// that give the same `hash()` are compared, if it has one too.
+    fn map_key(&self, map: &LoxMap, key: Literal, line: usize) -> Result<Literal, Error> {
+        let instance = match &key {
+            L::ClassInstance(instance) if instance.method("equals").is_some() => instance,
+            _ => return Ok(key),
+        };
+        let hash = |instance: &LoxInstance| self.call_hook(instance, "hash", Vec::new(), line);
+        let key_hash = hash(instance)?;
+
+        for (existing, _) in map.entries() {
+            let other = match &existing {
+                L::ClassInstance(other) if *other == *instance => return Ok(existing),
+                L::ClassInstance(other) => other,
+                _ => continue,
+            };
+            if key_hash.is_some() && hash(other)? != key_hash {
+                continue;
+            }
+
+            let equal = self.call_hook(instance, "equals", vec![existing.clone()], line)?;
+            if equal.is_some_and(|equal| evaluate_truthy(&equal)) {
+                return Ok(existing);
+            }
+        }
+
+        Ok(key)
+    }
+
+    /// How `print` writes a value, using the `toString()` method of any
+    /// instance that has one, even inside a list or map.
+    fn stringify(&self, value: &Literal, line: usize) -> Result<String, Error> {
+        value.write(&self.printing, false, &mut |instance| match self.call_hook(
+            instance,
+            "toString",
+            Vec::new(),
+            line,
+        )? {
+            Some(L::String(s)) => Ok(Some(s)),
+            Some(other) => Err(SingleError(format!(
+                "toString() must return a string, got {}.",
+                other
+            ))),
+            None => Ok(None),
+        })
+    }
+
+    /// Runs `value` if it's a getter read as a property, giving its result.
+    fn run_getter(&self, value: Literal, line: usize) -> Result<Literal, Error> {
+        match value {
+            L::Callable(
+                getter @ LoxCallable {
+                    callable:
+                        Callable::Function(Function {
+                            is_getter: true, ..
+                        }),
+                    ..
+                },
+            ) => self.call(getter, Vec::new(), line),
+            value => Ok(value),
+        }
+    }
+
+    /// The line an expression ends on.
+    fn line(&self, id: usize) -> usize {
+        self.spans.get(id).map_or(0, |span| span.end)
+    }
+
+    /// The line a call ends on, where its closing parenthesis is.
+    fn call_line(&self, expr: &CallExpr) -> usize {
+        self.line(expr.id)
+    }
+
+    fn call_arguments(&self, expr: &CallExpr) -> Result<(LoxCallable, Vec<Literal>), Error> {
+        let callee = self.evaluate(&expr.callee)?;
+
+        let mut arguments: Vec<Literal> = Vec::new();
+
+        for arg in &expr.arguments {
+            arguments.push(self.evaluate(&arg)?);
+        }
+
+        match callee {
+            L::Callable(f) => Ok((f, arguments)),
+            _ => Err(SingleError(format!(
+                "visit_call called with non function literal callee"
+            ))),
         }
     }
 
@@ -168,6 +598,20 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
 
         let operator = expr.operator.token_type;
 
+        if let L::ClassInstance(instance) = &left {
+            let line = self.line(expr.id);
+            for method in operator_methods(operator) {
+                let arguments = vec![right.clone()];
+                if let Some(result) = self.call_hook(instance, method, arguments, line)? {
+                    return Ok(match operator {
+                        TT::EqualEqual => L::Boolean(evaluate_truthy(&result)),
+                        TT::BangEqual => L::Boolean(!evaluate_truthy(&result)),
+                        _ => result,
+                    });
+                }
+            }
+        }
+
         match (left, operator, right) {
             // Math
             (L::Number(l), TT::Plus, L::Number(r)) => Ok(L::Number(l + r)),
@@ -176,7 +620,9 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
             (L::Number(l), TT::Star, L::Number(r)) => Ok(L::Number(l * r)),
 
             // String concatenation
-            (L::String(l), TT::Plus, L::String(r)) => Ok(L::String(format!("{}{}", l, r))),
+            (L::String(l), TT::Plus, L::String(r)) => {
+                self.check_string(L::String(format!("{}{}", l, r)))
+            }
 
             // Comparison operators
             (L::Number(l), TT::Greater, L::Number(r)) => Ok(L::Boolean(l > r)),
@@ -184,6 +630,8 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
             (L::Number(l), TT::Less, L::Number(r)) => Ok(L::Boolean(l < r)),
             (L::Number(l), TT::LessEqual, L::Number(r)) => Ok(L::Boolean(l <= r)),
 
+            (l, TT::Is, r) => Ok(L::Boolean(is_a(&l, &r)?)),
+
             // Equality operators
             (l, TT::EqualEqual, r) => Ok(L::Boolean(l == r)),
             (l, TT::BangEqual, r) => Ok(L::Boolean(l != r)),
@@ -196,27 +644,27 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
     }
 
     fn visit_call(&self, expr: &CallExpr) -> Result<Literal, Error> {
-        let callee = self.evaluate(&expr.callee)?;
-
-        let mut arguments: Vec<Literal> = Vec::new();
-
-        for arg in &expr.arguments {
-            arguments.push(self.evaluate(&arg)?);
-        }
-
-        match callee {
-            L::Callable(f) => self.call(f, arguments),
-            _ => Err(SingleError(format!(
-                "visit_call called with non function literal callee"
-            ))),
-        }
+        let (callable, arguments) = self.call_arguments(expr)?;
+        self.call(callable, arguments, self.call_line(expr))
     }
 
     fn visit_get(&self, expr: &GetExpr) -> Result<Literal, Error> {
+        check_private(&expr.object, &expr.name.lexeme)?;
+
         match self.evaluate(&expr.object)? {
-            L::ClassInstance(i) => Ok(i.get(&expr.name.lexeme)?),
+            L::ClassInstance(i) => {
+                let value = i.get(&expr.name.lexeme)?;
+                self.run_getter(value, self.line(expr.id))
+            }
+            L::Callable(LoxCallable {
+                callable: Callable::Class(class),
+                name,
+            }) => {
+                let this = L::Callable(LoxCallable::new(name, Callable::Class(class.clone())));
+                Ok(class.get(&expr.name.lexeme, this)?)
+            }
             _ => Err(Error::SingleError(
-                "Only instances have properties.".to_string(),
+                "Only instances and classes have properties.".to_string(),
             )),
         }
     }
@@ -225,8 +673,73 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
         self.evaluate(&expr.expression)
     }
 
+    fn visit_index(&self, expr: &IndexExpr) -> Result<Literal, Error> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+
+        match object {
+            L::List(list) => {
+                let index = list_index(&index)?;
+                list.get(index)
+                    .ok_or_else(|| SingleError(format!("List index {} out of range.", index)))
+            }
+            L::Map(map) => {
+                let key = self.map_key(&map, index, self.line(expr.id))?;
+                Ok(map.get(&key).unwrap_or(L::Nil))
+            }
+            L::ClassInstance(instance) => {
+                let line = self.line(expr.id);
+                self.call_hook(&instance, "__index__", vec![index], line)?
+                    .ok_or_else(|| "Instances need an __index__ method to be indexed.".into())
+            }
+            _ => Err("Only lists and maps can be indexed.".into()),
+        }
+    }
+
+    fn visit_index_set(&self, expr: &IndexSetExpr) -> Result<Literal, Error> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+
+        match object {
+            L::List(list) => {
+                let index = list_index(&index)?;
+                let value = self.evaluate(&expr.value)?;
+                list.set(index, value.clone())?;
+                Ok(value)
+            }
+            L::Map(map) => {
+                let value = self.evaluate(&expr.value)?;
+                let key = self.map_key(&map, index, self.line(expr.id))?;
+                map.set(key, value.clone());
+                Ok(value)
+            }
+            _ => Err("Only lists and maps can be indexed.".into()),
+        }
+    }
+
+    fn visit_list(&self, expr: &ListExpr) -> Result<Literal, Error> {
+        let mut elements = Vec::new();
+
+        for element in expr.elements.iter() {
+            elements.push(self.evaluate(element)?);
+        }
+
+        Ok(L::List(LoxList::new(elements)))
+    }
+
+    fn visit_map(&self, expr: &MapExpr) -> Result<Literal, Error> {
+        let map = LoxMap::new(Vec::new());
+
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            let (key, value) = (self.evaluate(key)?, self.evaluate(value)?);
+            map.set(self.map_key(&map, key, self.line(expr.id))?, value);
+        }
+
+        Ok(L::Map(map))
+    }
+
     fn visit_literal(&self, expr: &LiteralExpr) -> Result<Literal, Error> {
-        Ok(expr.value.clone())
+        self.check_string(expr.value.clone())
     }
 
     fn visit_logical(&self, expr: &LogicalExpr) -> Result<Literal, Error> {
@@ -245,13 +758,47 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
     }
 
     fn visit_set(&self, expr: &SetExpr) -> Result<Literal, Error> {
-        let mut object = match self.evaluate(&expr.object)? {
-            L::ClassInstance(o) => o,
-            _ => Err("Only instances have fields.")?,
+        check_private(&expr.object, &expr.name.lexeme)?;
+        let object = self.evaluate(&expr.object)?;
+        let class = match &object {
+            L::ClassInstance(_) => None,
+            L::Callable(LoxCallable {
+                callable: Callable::Class(class),
+                ..
+            }) => Some(class),
+            _ => Err("Only instances and classes have fields.")?,
         };
 
         let value = self.evaluate(&expr.value)?;
-        object.set(&expr.name.lexeme, value.clone());
+        let name = &expr.name.lexeme;
+        match (class, &object) {
+            (Some(class), _) => class.set(name, value.clone())?,
+            (None, L::ClassInstance(instance)) if instance.class_kind() == ClassKind::Enum => {
+                return Err(SingleError(format!(
+                    "Can't set fields on a variant of enum {}.",
+                    instance.class_name()
+                )));
+            }
+            (None, L::ClassInstance(instance)) => match instance.find_setter(name) {
+                Some(setter) => {
+                    let setter = Callable::Function(setter.bind(instance.clone()));
+                    let line = self.line(expr.id);
+                    self.call(
+                        LoxCallable::new(name.clone(), setter),
+                        vec![value.clone()],
+                        line,
+                    )?;
+                }
+                None if instance.find_method(name).is_some_and(|m| m.is_getter) => {
+                    return Err(SingleError(format!(
+                        "Can't set property '{}', which has a getter but no setter.",
+                        private::unscoped(name)
+                    )));
+                }
+                None => instance.clone().set(name, value.clone()),
+            },
+            (None, _) => unreachable!(),
+        }
         Ok(value)
     }
 
@@ -263,10 +810,13 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
                 "Undefined property '{}'.",
                 expr.method.lexeme
             ))),
-            Some(method) => Ok(Literal::Callable(LoxCallable::new(
-                expr.method.lexeme.to_string(),
-                Callable::Function(method.bind(object)),
-            ))),
+            Some(method) => self.run_getter(
+                Literal::Callable(LoxCallable::new(
+                    expr.method.lexeme.to_string(),
+                    Callable::Function(method.bind(object)),
+                )),
+                self.line(expr.id),
+            ),
         }
     }
 
@@ -280,6 +830,13 @@ impl expr::Visitor<Result<Literal, Error>> for Interpreter {
     fn visit_unary(&self, expr: &UnaryExpr) -> Result<Literal, Error> {
         let right = self.evaluate(&expr.right)?;
 
+        if let (TokenType::Minus, L::ClassInstance(instance)) = (expr.operator.token_type, &right) {
+            let line = self.line(expr.id);
+            if let Some(result) = self.call_hook(instance, "__neg__", Vec::new(), line)? {
+                return Ok(result);
+            }
+        }
+
         match (expr.operator.token_type, right) {
             (TokenType::Bang, v) => Ok(Literal::Boolean(!evaluate_truthy(&v))),
             (TokenType::Minus, Literal::Number(n)) => {
@@ -312,11 +869,11 @@ impl crate::stmt::Visitor<Result<(), Error>> for Interpreter {
     }
 
     fn visit_class(&self, stmt: &ClassStmt) -> Result<(), Error> {
-        let superclass = match &stmt.superclass {
+        let superclass_class = match &stmt.superclass {
             None => None,
             Some(expression) => match self.evaluate(&Expr::Variable(expression.clone()))? {
                 L::Callable(callable) => match callable.callable {
-                    Callable::Class(class) => Some(LoxInstance::new(class)),
+                    Callable::Class(class) => Some(class),
                     c => {
                         return Err(SingleError(format!(
                             "Superclass must be a class. got {:?}",
@@ -333,6 +890,20 @@ impl crate::stmt::Visitor<Result<(), Error>> for Interpreter {
             },
         };
 
+        let mut traits = Vec::new();
+        for t in stmt.traits.iter() {
+            match self.evaluate(&Expr::Variable(t.clone()))? {
+                L::Callable(LoxCallable {
+                    callable: Callable::Trait(t),
+                    ..
+                }) => traits.push(*t),
+                value => return Err(SingleError(format!("Can only use traits, got {}.", value))),
+            }
+        }
+
+        let superclass = superclass_class
+            .as_ref()
+            .map(|class| LoxInstance::new(class.as_ref().clone()));
         let name = stmt.name.lexeme.clone();
         self.environments.peek().define(&name, L::Nil);
 
@@ -355,10 +926,42 @@ impl crate::stmt::Visitor<Result<(), Error>> for Interpreter {
             methods.insert(method.name.lexeme.clone(), function);
         }
 
-        let class = LoxCallable::new(
-            name.clone(),
-            Callable::Class(Class::new(name.clone(), superclass.clone(), methods)),
-        );
+        for getter in stmt.getters.iter() {
+            let function = Function::new_getter(getter.body.clone(), self.environments.peek());
+            methods.insert(getter.name.lexeme.clone(), function);
+        }
+
+        let setters = stmt
+            .setters
+            .iter()
+            .map(|setter| {
+                let function = Function::new(
+                    setter.body.clone(),
+                    setter.params.clone(),
+                    self.environments.peek(),
+                );
+                (setter.name.lexeme.clone(), function)
+            })
+            .collect();
+
+        let class_methods = stmt
+            .class_methods
+            .iter()
+            .map(|method| {
+                let function = Function::new(
+                    method.body.clone(),
+                    method.params.clone(),
+                    self.environments.peek(),
+                );
+                (method.name.lexeme.clone(), function)
+            })
+            .collect();
+
+        let class = Class::new(name.clone(), superclass.clone(), methods)
+            .with_class_methods(class_methods, superclass_class.as_deref())
+            .with_setters(setters)
+            .with_traits(traits);
+        let class = LoxCallable::new(name.clone(), Callable::Class(Box::new(class)));
 
         if superclass.is_some() {
             self.environments.pop_scope();
@@ -368,6 +971,17 @@ impl crate::stmt::Visitor<Result<(), Error>> for Interpreter {
         Ok(())
     }
 
+    fn visit_enum(&self, stmt: &EnumStmt) -> Result<(), Error> {
+        let name = stmt.name.lexeme.clone();
+        let class = native::enums::class(&name, &stmt.variants);
+        let value = L::Callable(LoxCallable::new(
+            name.clone(),
+            Callable::Class(Box::new(class)),
+        ));
+        self.environments.peek().define(&name, value);
+        Ok(())
+    }
+
     fn visit_expression(&self, stmt: &ExpressionStmt) -> Result<(), Error> {
         self.evaluate(&stmt.expression).map(|_| ())
     }
@@ -398,14 +1012,71 @@ impl crate::stmt::Visitor<Result<(), Error>> for Interpreter {
         }
     }
 
+    fn visit_match(&self, stmt: &MatchStmt) -> Result<(), Error> {
+        let value = self.evaluate(&stmt.value)?;
+
+        for (pattern, arm) in stmt.patterns.iter().zip(stmt.arms.iter()) {
+            if expr::Visitor::visit_get(self, pattern)? == value {
+                return self.execute(arm);
+            }
+        }
+
+        match stmt.arms.get(stmt.patterns.len()) {
+            Some(otherwise) => self.execute(otherwise),
+            None => Err(SingleError(format!(
+                "No arm of the match covers {}.",
+                self.stringify(&value, self.line(stmt.id))?
+            ))),
+        }
+    }
+
     fn visit_print(&self, stmt: &PrintStmt) -> Result<(), Error> {
         let value = self.evaluate(&stmt.expression)?;
-        println!("{}", value);
+        let value = self.stringify(&value, self.line(stmt.id))?;
+
+        // one write per line, so a debugger gets each line as a whole
+        self.context
+            .streams
+            .output
+            .borrow_mut()
+            .write_all(format!("{}\n", value).as_bytes())
+            .map_err(|e| format!("Failed to write output: {}", e))?;
+
         Ok(())
     }
 
     fn visit_return(&self, stmt: &ReturnStmt) -> Result<(), Error> {
-        Err(ReturnValue(self.evaluate(&stmt.value)?))
+        match stmt.value.as_ref() {
+            Expr::Call(call) => {
+                let (callable, arguments) = self.call_arguments(call)?;
+                Err(Error::TailCall(callable, arguments, self.call_line(call)))
+            }
+            value => Err(ReturnValue(self.evaluate(value)?)),
+        }
+    }
+
+    fn visit_trait(&self, stmt: &TraitStmt) -> Result<(), Error> {
+        let methods = stmt
+            .methods
+            .iter()
+            .map(|method| {
+                let function = Function::new(
+                    method.body.clone(),
+                    method.params.clone(),
+                    self.environments.peek(),
+                );
+                (method.name.lexeme.clone(), function)
+            })
+            .collect();
+
+        let name = stmt.name.lexeme.clone();
+        let class = Class::new(name.clone(), None, methods);
+        let value = L::Callable(LoxCallable::new(
+            name.clone(),
+            Callable::Trait(Box::new(class)),
+        ));
+        self.environments.peek().define(&name, value);
+        Ok(())
     }
 
     fn visit_var(&self, stmt: &VarStmt) -> Result<(), Error> {
@@ -429,10 +1100,175 @@ impl crate::stmt::Visitor<Result<(), Error>> for Interpreter {
     }
 }
 
-fn evaluate_truthy(v: &Literal) -> bool {
+/// The resolver has already checked `this.#name` is used in the class that
+/// declares it, but code it didn't see could still reach for it.
+fn check_private(object: &Expr, name: &str) -> Result<(), Error> {
+    match private::is_private(name) && !matches!(object, Expr::This(_)) {
+        true => Err(SingleError(format!(
+            "Private member '{}' can only be used through 'this'.",
+            name
+        ))),
+        false => Ok(()),
+    }
+}
+
+/// The methods an instance on the left of `operator` can overload it with,
+/// in the order they're looked for.
+fn operator_methods(operator: TokenType) -> &'static [&'static str] {
+    match operator {
+        TT::Plus => &["__add__"],
+        TT::Minus => &["__sub__"],
+        TT::Star => &["__mul__"],
+        TT::Slash => &["__div__"],
+        TT::Less => &["__lt__"],
+        TT::LessEqual => &["__le__"],
+        TT::Greater => &["__gt__"],
+        TT::GreaterEqual => &["__ge__"],
+        TT::EqualEqual | TT::BangEqual => &["__eq__", "equals"],
+        _ => &[],
+    }
+}
+
+/// Whether `value` is an instance of `class` or one of its subclasses, of a
+/// class using the trait `class`, or of the built-in type it stands for.
+fn is_a(value: &Literal, class: &Literal) -> Result<bool, Error> {
+    let class = match class {
+        L::Callable(LoxCallable {
+            callable: Callable::Class(class) | Callable::Trait(class),
+            ..
+        }) => class,
+        _ => {
+            return Err(SingleError(format!(
+                "Right operand of 'is' must be a class or trait, got {}.",
+                class
+            )))
+        }
+    };
+
+    if class.kind == ClassKind::BuiltInType {
+        return Ok(match (class.name.as_ref(), value) {
+            ("Bool", L::Boolean(_))
+            | ("List", L::List(_))
+            | ("Map", L::Map(_))
+            | ("Nil", L::Nil)
+            | ("Number", L::Number(_))
+            | ("String", L::String(_)) => true,
+            ("Class", L::Callable(c)) => matches!(c.callable, Callable::Class(_)),
+            ("Function", L::Callable(c)) => {
+                matches!(c.callable, Callable::Function(_) | Callable::Native(_))
+            }
+            _ => false,
+        });
+    }
+
+    match value {
+        L::ClassInstance(instance) => Ok(instance.is_instance_of(class)),
+        _ => Ok(false),
+    }
+}
+
+/// Whether `value` is or holds a string longer than `max`. `containers` are
+/// the lists and maps already looked in, which a cycle would come back to.
+fn too_long(value: &Literal, max: usize, containers: &mut Vec<usize>) -> bool {
+    match value {
+        L::String(s) => s.chars().count() > max,
+        L::List(list) if !containers.contains(&list.address()) => {
+            containers.push(list.address());
+            list.elements().iter().any(|e| too_long(e, max, containers))
+        }
+        L::Map(map) if !containers.contains(&map.address()) => {
+            containers.push(map.address());
+            map.entries()
+                .iter()
+                .any(|(k, v)| too_long(k, max, containers) || too_long(v, max, containers))
+        }
+        _ => false,
+    }
+}
+
+fn list_index(index: &Literal) -> Result<usize, Error> {
+    match index {
+        L::Number(n) if n.fract().is_zero() && !n.is_sign_negative() => n
+            .to_usize()
+            .ok_or_else(|| SingleError(format!("List index {} out of range.", n))),
+        _ => Err(SingleError(format!(
+            "List index must be a whole number, got {}.",
+            index
+        ))),
+    }
+}
+
+pub(crate) fn evaluate_truthy(v: &Literal) -> bool {
     match v {
         Literal::Nil => false,
         Literal::Boolean(b) => *b,
         _ => true,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        io::{self, Write},
+        rc::Rc,
+    };
+
+    use super::Options;
+    use crate::native::Streams;
+
+    /// A `Vec<u8>` sink the test keeps a handle on, to read back what the
+    /// script wrote once the interpreter has dropped its streams.
+    #[derive(Clone, Default)]
+    struct Sink(Rc<RefCell<Vec<u8>>>);
+
+    impl Sink {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(source: &str, input: &'static [u8]) -> (String, String) {
+        let (output, error) = (Sink::default(), Sink::default());
+        let streams = Streams::new(output.clone(), error.clone(), input);
+
+        crate::run(
+            crate::init_globals(),
+            source.to_string(),
+            false,
+            &Options::default(),
+            streams,
+        )
+        .unwrap();
+
+        (output.text(), error.text())
+    }
+
+    #[test]
+    fn writes_to_the_given_streams() {
+        let (output, error) = run("print 1 + 2;\nstderr(\"oops\");\nprint \"done\";\n", b"");
+
+        assert_eq!(output, "3\ndone\n");
+        assert_eq!(error, "oops\n");
+    }
+
+    #[test]
+    fn reads_from_the_given_stream() {
+        let (output, _) = run(
+            "var name = input(\"Name? \");\nprint readLine();\nprint readLine();\nprint name;\n",
+            b"Ada\r\nline\n",
+        );
+
+        assert_eq!(output, "Name? line\nnil\nAda\n");
+    }
+}
@@ -0,0 +1,507 @@
+use std::{
+    fmt::{Display, Write as _},
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use rust_decimal::Decimal;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(Decimal),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub(crate) fn object(entries: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) => n.to_string().parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    /// Writes the value across several lines, with each level of nesting
+    /// indented by another `indent` spaces.
+    pub(crate) fn pretty(&self, indent: usize) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, indent, 0);
+        out
+    }
+
+    fn write_pretty(&self, out: &mut String, indent: usize, depth: usize) {
+        let (open, close, len) = match self {
+            Json::Array(items) if !items.is_empty() => ('[', ']', items.len()),
+            Json::Object(entries) if !entries.is_empty() => ('{', '}', entries.len()),
+            value => {
+                _ = write!(out, "{}", value);
+                return;
+            }
+        };
+
+        let padding = " ".repeat(indent * (depth + 1));
+        out.push(open);
+
+        for i in 0..len {
+            out.push_str(if i > 0 { ",\n" } else { "\n" });
+            out.push_str(&padding);
+
+            match self {
+                Json::Array(items) => items[i].write_pretty(out, indent, depth + 1),
+                Json::Object(entries) => {
+                    let (key, value) = &entries[i];
+                    _ = write!(out, "{}: ", Json::String(key.clone()));
+                    value.write_pretty(out, indent, depth + 1);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        out.push('\n');
+        out.push_str(&" ".repeat(indent * depth));
+        out.push(close);
+    }
+}
+
+/// Reads one message framed with a `Content-Length` header, as used by both
+/// the Debug Adapter and Language Server protocols. Returns `None` once the
+/// input ends, and an error for a message that can't be read, after which the
+/// next message can still be read.
+pub(crate) fn read_message(input: &mut impl BufRead) -> Option<Result<Json, String>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+
+        match header.trim() {
+            "" => break,
+            h => {
+                if let Some((_, value)) = h.split_once("Content-Length:") {
+                    length = value.trim().parse().ok();
+                }
+            }
+        }
+    }
+
+    let length = match length {
+        Some(length) => length,
+        None => return Some(Err("Missing Content-Length header".into())),
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).ok()?;
+
+    Some(match String::from_utf8(body) {
+        Ok(body) => body.parse(),
+        Err(_) => Err("Message is not valid UTF-8".into()),
+    })
+}
+
+pub(crate) fn write_message(output: &mut impl Write, message: &Json) {
+    let body = message.to_string();
+    _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    _ = output.flush();
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(Decimal::from(n))
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Self {
+        Json::Number(Decimal::from(n))
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_escaped(f, s),
+            Json::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(entries) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_escaped(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_escaped(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+
+    f.write_str("\"")
+}
+
+impl FromStr for Json {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            current: 0,
+            depth: 0,
+        };
+
+        let value = parser.value()?;
+        parser.skip_whitespace();
+
+        match parser.is_at_end() {
+            true => Ok(value),
+            false => Err(parser.error("Unexpected trailing characters")),
+        }
+    }
+}
+
+/// How deeply arrays and objects may nest, so that parsing can't run out of
+/// stack.
+const MAX_DEPTH: usize = 512;
+
+struct Parser {
+    chars: Vec<char>,
+    current: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[' | '{') if self.depth == MAX_DEPTH => Err(self.error(&format!(
+                "Arrays and objects nest more than {} deep",
+                MAX_DEPTH
+            ))),
+            Some(c @ ('[' | '{')) => {
+                self.depth += 1;
+                let value = match c {
+                    '[' => self.array(),
+                    _ => self.object(),
+                };
+                self.depth -= 1;
+                value
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(&format!("Unexpected character '{}'", c))),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            match self.advance() {
+                Some(c) if c == expected => (),
+                _ => return Err(self.error(&format!("Expected '{}'", word))),
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.current;
+
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' | '-' | '+' | '.' | 'e' | 'E' => self.current += 1,
+                _ => break,
+            }
+        }
+
+        let text: String = self.chars[start..self.current].iter().collect();
+
+        let value = match text.contains(['e', 'E']) {
+            true => Decimal::from_scientific(&text),
+            false => Decimal::from_str(&text),
+        };
+
+        match value {
+            Ok(number) if is_number(&text) => Ok(Json::Number(number)),
+            _ => {
+                self.current = start;
+                Err(self.error(&format!("Invalid number '{}'", text)))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        // the opening "
+        self.advance();
+        let mut value = String::new();
+
+        loop {
+            match self.advance() {
+                None => return Err(self.error("Unterminated string")),
+                Some('"') => return Ok(value),
+                Some('\\') => match self.advance() {
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('/') => value.push('/'),
+                    Some('b') => value.push('\u{8}'),
+                    Some('f') => value.push('\u{c}'),
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some('u') => value.push(self.unicode_escape()?),
+                    _ => return Err(self.error("Invalid escape sequence")),
+                },
+                Some(c) if c < ' ' => {
+                    self.current -= 1;
+                    return Err(self.error("Unescaped control character in string"));
+                }
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("Invalid unicode escape"));
+        }
+
+        // a surrogate pair is written as two consecutive escapes
+        if self.advance() != Some('\\') || self.advance() != Some('u') {
+            return Err(self.error("Expected low surrogate"));
+        }
+
+        let low = self.hex4()?;
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+        char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            match self.advance().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("Invalid unicode escape")),
+            }
+        }
+
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        // the opening [
+        self.advance();
+        let mut items = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.advance();
+            return Ok(Json::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.advance() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(items)),
+                _ => {
+                    self.current -= 1;
+                    return Err(self.error("Expected ',' or ']' in array"));
+                }
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        // the opening {
+        self.advance();
+        let mut entries = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.advance();
+            return Ok(Json::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("Expected string key in object"));
+            }
+
+            let key = self.string()?;
+            self.skip_whitespace();
+
+            if self.advance() != Some(':') {
+                self.current -= 1;
+                return Err(self.error("Expected ':' after object key"));
+            }
+
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+
+            match self.advance() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(entries)),
+                _ => {
+                    self.current -= 1;
+                    return Err(self.error("Expected ',' or '}' in object"));
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.current += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).cloned()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.current += 1;
+        c
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.chars.len()
+    }
+
+    fn error(&self, message: &str) -> String {
+        let consumed = &self.chars[..self.current.min(self.chars.len())];
+        let line = consumed.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = consumed.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+
+        format!("{} at line {} column {}", message, line, column)
+    }
+}
+
+/// Whether `text` follows JSON's grammar for numbers, which has no leading
+/// zeros, `+` signs or points without digits on both sides.
+fn is_number(text: &str) -> bool {
+    let digits = |s: &str| s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    let rest = text.strip_prefix('-').unwrap_or(text);
+    let integer = digits(rest);
+    if integer == 0 || (integer > 1 && rest.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &rest[integer..];
+
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let count = digits(fraction);
+        if count == 0 {
+            return false;
+        }
+        rest = &fraction[count..];
+    }
+
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let count = digits(exponent);
+        if count == 0 {
+            return false;
+        }
+        rest = &exponent[count..];
+    }
+
+    rest.is_empty()
+}
@@ -0,0 +1,693 @@
+use std::{collections::HashMap, io};
+
+use crate::{
+    init_globals,
+    json::{self, Json},
+    parser::{self, Spans},
+    resolver::{self, Symbol, SymbolKind, Symbols},
+    tokens::{self, Callable, Literal, LoxCallable, Token, TokenType, Tokens},
+};
+
+/// Runs a Language Server Protocol server on stdin/stdout until the client
+/// sends `exit`.
+pub(crate) fn serve() -> i32 {
+    let mut server = Server {
+        documents: HashMap::new(),
+        shutdown: false,
+    };
+    let mut stdin = io::stdin().lock();
+
+    while let Some(message) = json::read_message(&mut stdin) {
+        let message = match message {
+            Ok(message) => message,
+            Err(error) => {
+                send(Json::object(vec![
+                    ("jsonrpc", "2.0".into()),
+                    ("id", Json::Null),
+                    (
+                        "error",
+                        Json::object(vec![
+                            ("code", Json::Number((-32700).into())),
+                            ("message", error.into()),
+                        ]),
+                    ),
+                ]));
+                continue;
+            }
+        };
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+
+        if method == "exit" {
+            break;
+        }
+
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+
+        match message.get("id") {
+            Some(id) => {
+                let response = match server.request(method, &params) {
+                    Some(result) => Json::object(vec![
+                        ("jsonrpc", "2.0".into()),
+                        ("id", id.clone()),
+                        ("result", result),
+                    ]),
+                    None => Json::object(vec![
+                        ("jsonrpc", "2.0".into()),
+                        ("id", id.clone()),
+                        (
+                            "error",
+                            Json::object(vec![
+                                ("code", Json::Number((-32601).into())),
+                                ("message", format!("Unhandled method {}", method).into()),
+                            ]),
+                        ),
+                    ]),
+                };
+                send(response);
+            }
+            None => server.notification(method, &params),
+        }
+    }
+
+    match server.shutdown {
+        true => 0,
+        false => 1,
+    }
+}
+
+fn send(message: Json) {
+    json::write_message(&mut io::stdout().lock(), &message);
+}
+
+struct Document {
+    text: String,
+    tokens: Vec<Token>,
+    spans: Option<Spans>,
+    symbols: Symbols,
+    errors: Vec<String>,
+}
+
+impl Document {
+    fn analyze(text: String) -> Document {
+        let mut document = Document {
+            text,
+            tokens: Vec::new(),
+            spans: None,
+            symbols: Symbols::new(),
+            errors: Vec::new(),
+        };
+
+        let tokens: Tokens = match document.text.parse() {
+            Ok(tokens) => tokens,
+            Err(errors) => {
+                document.errors = errors;
+                return document;
+            }
+        };
+        document.tokens = tokens.iter().cloned().collect();
+
+        let (statements, spans) = match parser::parse_with_spans(tokens) {
+            Ok(parsed) => parsed,
+            Err(errors) => {
+                document.errors = errors;
+                return document;
+            }
+        };
+
+        let (symbols, errors) = resolver::resolve_symbols(&statements, &spans);
+        document.symbols = symbols;
+        document.errors = errors;
+        document.spans = Some(spans);
+        document
+    }
+
+    fn line(&self, line: usize) -> &str {
+        self.text.lines().nth(line).unwrap_or("")
+    }
+
+    /// The line and the character on it of a position, both counting from 0.
+    /// Clients count characters in UTF-16 code units, and tokens in chars.
+    fn cursor(&self, position: &Json) -> (usize, usize) {
+        let line = position.get("line").and_then(Json::as_usize).unwrap_or(0);
+        let units = position
+            .get("character")
+            .and_then(Json::as_usize)
+            .unwrap_or(0);
+
+        let mut counted = 0;
+        let character = self
+            .line(line)
+            .chars()
+            .take_while(|c| {
+                counted += c.len_utf16();
+                counted <= units
+            })
+            .count();
+
+        (line, character)
+    }
+
+    /// The position of a character on a line, counting both from 0.
+    fn position(&self, line: usize, character: usize) -> Json {
+        let units: usize = self
+            .line(line)
+            .chars()
+            .take(character)
+            .map(char::len_utf16)
+            .sum();
+        position(line, units)
+    }
+
+    fn range(&self, token: &Token) -> Json {
+        let line = token.line_number.saturating_sub(1);
+        let start = token.column.saturating_sub(1);
+
+        Json::object(vec![
+            ("start", self.position(line, start)),
+            (
+                "end",
+                self.position(line, start + token.lexeme.chars().count()),
+            ),
+        ])
+    }
+
+    fn location(&self, uri: &str, token: &Token) -> Json {
+        Json::object(vec![("uri", uri.into()), ("range", self.range(token))])
+    }
+
+    fn token_at(&self, position: &Json) -> Option<(usize, &Token)> {
+        let (line, character) = self.cursor(position);
+        let (line, character) = (line + 1, character + 1);
+
+        self.tokens.iter().enumerate().find(|(_, t)| {
+            t.token_type == TokenType::Identifier
+                && t.line_number == line
+                && t.column <= character
+                && character <= t.column + t.lexeme.chars().count()
+        })
+    }
+
+    /// The symbols the identifier under the cursor could refer to. Property
+    /// names can't be resolved statically, so they match every method with
+    /// that name.
+    fn symbols_at(&self, position: &Json) -> Vec<&Symbol> {
+        let (index, token) = match self.token_at(position) {
+            Some(found) => found,
+            None => return Vec::new(),
+        };
+
+        let is_property = index > 0 && self.tokens[index - 1].token_type == TokenType::Dot;
+
+        match is_property {
+            true => self
+                .symbols
+                .iter()
+                .filter(|s| s.kind == SymbolKind::Method && s.declaration.lexeme == token.lexeme)
+                .collect(),
+            false => self.symbols_of(token).collect(),
+        }
+    }
+
+    /// The symbols declared or referred to by an identifier.
+    fn symbols_of<'a>(&'a self, token: &'a Token) -> impl Iterator<Item = &'a Symbol> {
+        self.symbols.iter().filter(|s| {
+            same_position(&s.declaration, token)
+                || s.references.iter().any(|r| same_position(r, token))
+        })
+    }
+
+    fn in_scope(&self, symbol: &Symbol, line: usize) -> bool {
+        match (symbol.scope, &self.spans) {
+            (None, _) => true,
+            (Some(owner), Some(spans)) => match spans.get(owner) {
+                Some(span) => {
+                    span.start <= line && line <= span.end && symbol.declaration.line_number <= line
+                }
+                None => false,
+            },
+            (Some(_), None) => false,
+        }
+    }
+
+    fn is_after_dot(&self, line: usize, character: usize) -> bool {
+        let before: Vec<char> = self.line(line).chars().take(character).collect();
+
+        before
+            .iter()
+            .rev()
+            .find(|c| !(c.is_alphanumeric() || **c == '_'))
+            .is_some_and(|&c| c == '.')
+    }
+
+    /// The class of the value before the `.` at the cursor and its
+    /// superclasses, when the value is `this`, `super`, a class, or a
+    /// variable initialized by calling a class.
+    fn receiver_classes(&self, line: usize, character: usize) -> Option<Vec<String>> {
+        let before = self
+            .tokens
+            .iter()
+            .take_while(|t| {
+                (t.line_number.saturating_sub(1), t.column.saturating_sub(1)) < (line, character)
+            })
+            .count();
+        // the name being typed after the dot
+        let dot = match self.tokens[..before].last() {
+            Some(t) if t.token_type == TokenType::Identifier => before - 1,
+            _ => before,
+        }
+        .checked_sub(1)?;
+        let receiver = self.tokens[..dot].last()?;
+
+        let class = match receiver.token_type {
+            TokenType::This => self.enclosing_class(dot)?,
+            TokenType::Super => {
+                let class = self.enclosing_class(dot)?;
+                self.class(&class)?.superclass.as_ref()?.lexeme.clone()
+            }
+            TokenType::Identifier => self.class_of(receiver)?,
+            _ => return None,
+        };
+
+        let mut classes = vec![class];
+        while let Some(superclass) = classes
+            .last()
+            .and_then(|c| self.class(c))
+            .and_then(|c| c.superclass.as_ref())
+        {
+            if classes.contains(&superclass.lexeme) {
+                break;
+            }
+            classes.push(superclass.lexeme.clone());
+        }
+
+        Some(classes)
+    }
+
+    fn class(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| {
+            matches!(s.kind, SymbolKind::Class | SymbolKind::Trait) && s.declaration.lexeme == name
+        })
+    }
+
+    /// The class whose body holds the token at `index`, found by going back
+    /// over the blocks around it to the `class Name ... {` that opens one.
+    fn enclosing_class(&self, index: usize) -> Option<String> {
+        let mut depth = 0;
+
+        for (i, token) in self.tokens[..index].iter().enumerate().rev() {
+            match token.token_type {
+                TokenType::RightBrace => depth += 1,
+                TokenType::LeftBrace if depth > 0 => depth -= 1,
+                TokenType::LeftBrace => {
+                    let header = self.tokens[..i].iter().rev().position(|t| {
+                        !matches!(
+                            t.token_type,
+                            TokenType::Identifier
+                                | TokenType::Less
+                                | TokenType::With
+                                | TokenType::Comma
+                        )
+                    });
+                    let start = i - header.unwrap_or(i);
+                    if start > 0 && self.tokens[start - 1].token_type == TokenType::Class {
+                        return Some(self.tokens[start].lexeme.clone());
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        None
+    }
+
+    /// The class an identifier names, or that the variable it refers to was
+    /// initialized by calling, as in `var a = Name(...)`.
+    fn class_of(&self, identifier: &Token) -> Option<String> {
+        let symbol = self.symbols_of(identifier).next()?;
+
+        match symbol.kind {
+            SymbolKind::Class | SymbolKind::Trait => Some(symbol.declaration.lexeme.clone()),
+            SymbolKind::Variable => {
+                let declared = self
+                    .tokens
+                    .iter()
+                    .position(|t| same_position(t, &symbol.declaration))?;
+
+                match self.tokens.get(declared + 1..declared + 4)? {
+                    [equal, class, paren]
+                        if equal.token_type == TokenType::Equal
+                            && paren.token_type == TokenType::LeftParen
+                            && self.class(&class.lexeme).is_some() =>
+                    {
+                        Some(class.lexeme.clone())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+impl Server {
+    fn request(&mut self, method: &str, params: &Json) -> Option<Json> {
+        if method == "initialize" {
+            return Some(initialize_result());
+        }
+
+        if method == "shutdown" {
+            self.shutdown = true;
+            return Some(Json::Null);
+        }
+
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .unwrap_or("");
+        let position = params.get("position").cloned().unwrap_or(Json::Null);
+
+        let document = match self.documents.get(uri) {
+            Some(document) => document,
+            None => return Some(Json::Null),
+        };
+
+        match method {
+            "textDocument/definition" => Some(Json::Array(
+                document
+                    .symbols_at(&position)
+                    .iter()
+                    .map(|s| document.location(uri, &s.declaration))
+                    .collect(),
+            )),
+            "textDocument/references" => {
+                let include_declaration = params
+                    .get("context")
+                    .and_then(|c| c.get("includeDeclaration"))
+                    .and_then(Json::as_bool)
+                    .unwrap_or(true);
+
+                let mut locations = Vec::new();
+                for symbol in document.symbols_at(&position) {
+                    if include_declaration {
+                        locations.push(document.location(uri, &symbol.declaration));
+                    }
+                    for reference in symbol.references.iter() {
+                        locations.push(document.location(uri, reference));
+                    }
+                }
+
+                Some(Json::Array(locations))
+            }
+            "textDocument/hover" => {
+                let contents: Vec<String> = document
+                    .symbols_at(&position)
+                    .iter()
+                    .map(|s| describe(s, &document.symbols))
+                    .collect();
+
+                Some(match contents.is_empty() {
+                    true => Json::Null,
+                    false => Json::object(vec![(
+                        "contents",
+                        Json::object(vec![
+                            ("kind", "markdown".into()),
+                            ("value", contents.join("\n\n---\n\n").into()),
+                        ]),
+                    )]),
+                })
+            }
+            "textDocument/documentSymbol" => Some(document_symbols(document)),
+            "textDocument/completion" => Some(completions(document, &position)),
+            _ => None,
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) {
+        let document = params.get("textDocument");
+        let uri = document
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        let text = match method {
+            "textDocument/didOpen" => document.and_then(|d| d.get("text")),
+            // we ask for full document sync, so the last change is the whole text
+            "textDocument/didChange" => params
+                .get("contentChanges")
+                .and_then(|c| c.as_array().last())
+                .and_then(|c| c.get("text")),
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                publish_diagnostics(&uri, &[]);
+                return;
+            }
+            _ => return,
+        };
+
+        let document = Document::analyze(text.and_then(Json::as_str).unwrap_or("").to_string());
+        publish_diagnostics(&uri, &document.errors);
+        self.documents.insert(uri, document);
+    }
+}
+
+fn initialize_result() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("positionEncoding", "utf-16".into()),
+                ("textDocumentSync", 1.into()),
+                ("definitionProvider", true.into()),
+                ("referencesProvider", true.into()),
+                ("hoverProvider", true.into()),
+                ("documentSymbolProvider", true.into()),
+                (
+                    "completionProvider",
+                    Json::object(vec![("triggerCharacters", Json::Array(vec![".".into()]))]),
+                ),
+            ]),
+        ),
+        ("serverInfo", Json::object(vec![("name", "rlox".into())])),
+    ])
+}
+
+fn publish_diagnostics(uri: &str, errors: &[String]) {
+    let diagnostics = errors
+        .iter()
+        .map(|error| {
+            let (line, message) = split_error_line(error);
+
+            Json::object(vec![
+                (
+                    "range",
+                    Json::object(vec![
+                        ("start", position(line.saturating_sub(1), 0)),
+                        ("end", position(line, 0)),
+                    ]),
+                ),
+                ("severity", 1.into()),
+                ("source", "rlox".into()),
+                ("message", message.into()),
+            ])
+        })
+        .collect();
+
+    send(Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            Json::object(vec![
+                ("uri", uri.into()),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ),
+    ]));
+}
+
+/// Splits the `[line N]` prefix the scanner, parser and resolver put on their
+/// errors.
+fn split_error_line(error: &str) -> (usize, &str) {
+    error
+        .strip_prefix("[line ")
+        .and_then(|rest| rest.split_once("] "))
+        .and_then(|(line, message)| line.parse().ok().map(|line| (line, message)))
+        .unwrap_or((1, error))
+}
+
+fn same_position(a: &Token, b: &Token) -> bool {
+    a.line_number == b.line_number && a.column == b.column
+}
+
+fn position(line: usize, character: usize) -> Json {
+    Json::object(vec![("line", line.into()), ("character", character.into())])
+}
+
+fn signature(symbol: &Symbol) -> String {
+    let params: Vec<&str> = symbol.params.iter().map(|p| p.lexeme.as_str()).collect();
+    format!("{}({})", symbol.declaration.lexeme, params.join(", "))
+}
+
+fn describe(symbol: &Symbol, symbols: &Symbols) -> String {
+    let name = &symbol.declaration.lexeme;
+
+    let (code, arity) = match symbol.kind {
+        SymbolKind::Function => (
+            format!("fun {}", signature(symbol)),
+            Some(symbol.params.len()),
+        ),
+        SymbolKind::Method => (
+            format!(
+                "{}.{}",
+                symbol.container.as_deref().unwrap_or(""),
+                signature(symbol)
+            ),
+            Some(symbol.params.len()),
+        ),
+        SymbolKind::Class => {
+            let initializer = symbols.iter().find(|s| {
+                s.kind == SymbolKind::Method
+                    && s.container.as_deref() == Some(name)
+                    && s.declaration.lexeme == "init"
+            });
+
+            let code = match &symbol.superclass {
+                Some(superclass) => format!("class {} < {}", name, superclass.lexeme),
+                None => format!("class {}", name),
+            };
+            (code, Some(initializer.map_or(0, |i| i.params.len())))
+        }
+        SymbolKind::Enum => (format!("enum {}", name), None),
+        SymbolKind::Parameter => (format!("(parameter) {}", name), None),
+        SymbolKind::Trait => (format!("trait {}", name), None),
+        SymbolKind::Variable => (format!("var {}", name), None),
+    };
+
+    match arity {
+        Some(arity) => format!("```lox\n{}\n```\narity {}", code, arity),
+        None => format!("```lox\n{}\n```", code),
+    }
+}
+
+fn document_symbols(document: &Document) -> Json {
+    let symbol = |s: &Symbol, kind: usize, children: Vec<Json>| {
+        let detail = match s.kind {
+            SymbolKind::Class | SymbolKind::Trait => s
+                .superclass
+                .as_ref()
+                .map_or(String::new(), |c| format!("< {}", c.lexeme)),
+            SymbolKind::Enum => String::new(),
+            _ => signature(s),
+        };
+
+        Json::object(vec![
+            ("name", s.declaration.lexeme.as_str().into()),
+            ("detail", detail.into()),
+            ("kind", kind.into()),
+            ("range", document.range(&s.declaration)),
+            ("selectionRange", document.range(&s.declaration)),
+            ("children", Json::Array(children)),
+        ])
+    };
+
+    let symbols = document
+        .symbols
+        .iter()
+        .filter(|s| s.scope.is_none())
+        .filter_map(|s| match s.kind {
+            SymbolKind::Class | SymbolKind::Trait => {
+                let methods = document
+                    .symbols
+                    .iter()
+                    .filter(|m| {
+                        m.kind == SymbolKind::Method
+                            && m.container.as_deref() == Some(&s.declaration.lexeme)
+                            && m.declaration.line_number >= s.declaration.line_number
+                    })
+                    .map(|m| symbol(m, 6, Vec::new()))
+                    .collect();
+                let kind = match s.kind {
+                    SymbolKind::Trait => 11,
+                    _ => 5,
+                };
+                Some(symbol(s, kind, methods))
+            }
+            SymbolKind::Enum => Some(symbol(s, 10, Vec::new())),
+            SymbolKind::Function => Some(symbol(s, 12, Vec::new())),
+            _ => None,
+        })
+        .collect();
+
+    Json::Array(symbols)
+}
+
+fn completions(document: &Document, position: &Json) -> Json {
+    let mut items: Vec<(String, usize)> = Vec::new();
+    let (line, character) = document.cursor(position);
+
+    if document.is_after_dot(line, character) {
+        // every method, when the receiver's class isn't known
+        let classes = document.receiver_classes(line, character);
+
+        for symbol in document.symbols.iter() {
+            let listed = match (&classes, &symbol.container) {
+                (None, _) => true,
+                (Some(classes), Some(container)) => classes.contains(container),
+                (Some(_), None) => false,
+            };
+            if symbol.kind == SymbolKind::Method && listed {
+                items.push((symbol.declaration.lexeme.clone(), 2));
+            }
+        }
+    } else {
+        let line = line + 1;
+
+        for symbol in document.symbols.iter() {
+            let kind = match symbol.kind {
+                SymbolKind::Method => continue,
+                SymbolKind::Class => 7,
+                SymbolKind::Enum => 13,
+                SymbolKind::Function => 3,
+                SymbolKind::Trait => 8,
+                SymbolKind::Parameter | SymbolKind::Variable => 6,
+            };
+
+            if document.in_scope(symbol, line) {
+                items.push((symbol.declaration.lexeme.clone(), kind));
+            }
+        }
+
+        for (name, value) in init_globals().values() {
+            let kind = match value {
+                Literal::Callable(LoxCallable {
+                    callable: Callable::Class(_),
+                    ..
+                }) => 7,
+                Literal::Callable(_) => 3,
+                _ => 6,
+            };
+            items.push((name, kind));
+        }
+
+        for keyword in tokens::keywords() {
+            items.push((keyword.to_string(), 14));
+        }
+    }
+
+    items.sort();
+    items.dedup_by(|a, b| a.0 == b.0);
+
+    Json::Array(
+        items
+            .into_iter()
+            .map(|(label, kind)| Json::object(vec![("label", label.into()), ("kind", kind.into())]))
+            .collect(),
+    )
+}
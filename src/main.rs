@@ -3,63 +3,323 @@
 extern crate lazy_static;
 
 mod ast_printer;
+mod dap;
 mod environment;
 mod expr;
+mod formatter;
+mod gc;
 mod interpreter;
+mod json;
+mod lsp;
 mod native;
+mod optimizer;
 mod parser;
 mod resolver;
 mod stmt;
 mod tokens;
 
-use std::{env, fs, io, process};
+use std::{env, fs, io, process, str::FromStr, thread, time::Duration};
 
 use environment::Environment;
-use stmt::Stmt;
+use interpreter::{Options, RuntimeError};
+use native::{Streams, VirtualClock};
 use tokens::Tokens;
 
+/// Rust stack reserved for each Lox call, so scripts hit the call depth limit
+/// long before the interpreter runs out of stack.
+const STACK_PER_CALL: usize = 64 * 1024;
+const MIN_STACK_SIZE: usize = 8 * 1024 * 1024;
+/// What `--deterministic` seeds the random natives with, unless `--seed` is given.
+const DETERMINISTIC_SEED: u64 = 0;
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut optimize = false;
+    let mut gc_stats = false;
+    let mut deterministic = false;
+    let mut clock = VirtualClock::default();
+    let mut options = Options::default();
+    let mut args: Vec<String> = Vec::new();
+
+    for arg in env::args() {
+        // everything after the script or command is theirs
+        if args.len() > 1 {
+            args.push(arg);
+        } else if arg == "-O" {
+            optimize = true;
+        } else if arg == "--gc-stress" {
+            options.gc_stress = true;
+        } else if arg == "--gc-stats" {
+            gc_stats = true;
+        } else if arg == "--deterministic" {
+            deterministic = true;
+        } else if let Some(seconds) = arg.strip_prefix("--clock-per-call=") {
+            deterministic = true;
+            clock.per_call = limit("clock advance", seconds);
+        } else if let Some(seconds) = arg.strip_prefix("--clock-per-step=") {
+            deterministic = true;
+            clock.per_step = limit("clock advance", seconds);
+        } else if arg == "--no-fs" {
+            options.allow_fs = false;
+        } else if let Some(depth) = arg.strip_prefix("--max-call-depth=") {
+            options.max_call_depth = limit("call depth", depth);
+        } else if let Some(steps) = arg.strip_prefix("--max-steps=") {
+            options.max_steps = Some(limit("step budget", steps));
+        } else if let Some(timeout) = arg.strip_prefix("--timeout=") {
+            options.timeout = Some(Duration::from_millis(limit("timeout", timeout)));
+        } else if let Some(objects) = arg.strip_prefix("--max-heap=") {
+            options.max_heap = Some(limit("heap size", objects));
+        } else if let Some(length) = arg.strip_prefix("--max-string-length=") {
+            options.max_string_length = Some(limit("string length", length));
+        } else if let Some(seed) = arg.strip_prefix("--seed=") {
+            options.seed = Some(limit("seed", seed));
+        } else if let Some(natives) = arg.strip_prefix("--allow-natives=") {
+            options.allowed_natives = Some(
+                natives
+                    .split(',')
+                    .filter(|n| !n.is_empty())
+                    .map(String::from)
+                    .collect(),
+            );
+        } else {
+            args.push(arg);
+        }
+    }
+
+    if deterministic {
+        options.seed.get_or_insert(DETERMINISTIC_SEED);
+        options.virtual_clock = Some(clock);
+    }
+
+    // the interpreter recurses on the Rust stack for every Lox call
+    let stack_size = MIN_STACK_SIZE.max(options.max_call_depth.saturating_mul(STACK_PER_CALL));
+    let main = thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || run_main(args, optimize, gc_stats, options));
+
+    match main.map(|main| main.join()) {
+        Ok(Ok(())) => {}
+        Ok(Err(_)) => process::exit(101),
+        Err(e) => {
+            eprintln!("Failed to start the interpreter: {}", e);
+            process::exit(70);
+        }
+    }
+}
 
-    if args.len() > 2 {
-        eprintln!(" usage: rlox [script]");
+fn limit<T: FromStr>(name: &str, value: &str) -> T {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid {} '{}'", name, value);
         process::exit(64);
+    })
+}
+
+fn run_main(args: Vec<String>, optimize: bool, gc_stats: bool, options: Options) {
+    if args.get(1).map(String::as_str) == Some("debug") {
+        let options = Options {
+            args: args.get(3..).unwrap_or_default().to_vec(),
+            ..options
+        };
+        if let Err(error) = dap::serve(args.get(2).cloned(), options) {
+            eprintln!("Error running debugger: {}", error);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    if args.get(1).map(String::as_str) == Some("lsp") {
+        process::exit(lsp::serve());
+    }
+
+    if args.get(1).map(String::as_str) == Some("fmt") {
+        process::exit(run_fmt(&args[2..]));
     }
 
-    if args.len() == 2 {
-        match run_file(args.last().unwrap()) {
+    if let Some(flag) = args.get(1).filter(|a| a.starts_with("--dump-")) {
+        process::exit(run_dump(flag, args.get(2), optimize, &options));
+    }
+
+    if args.get(1).is_some_and(|a| a.starts_with('-')) {
+        eprintln!(" usage: rlox [-O] [--gc-stress] [--gc-stats] [determinism] [limits] [script [args...]]");
+        eprintln!("        rlox --dump-tokens[=sexp|json] [script]");
+        eprintln!("        rlox [-O] --dump-ast[=sexp|json] [script]");
+        eprintln!("        rlox fmt [--check] [script...]");
+        eprintln!("        rlox debug [script]");
+        eprintln!("        rlox lsp");
+        eprintln!();
+        eprintln!(" determinism: --seed=N --deterministic --clock-per-call=S --clock-per-step=S");
+        eprintln!(" limits: --max-call-depth=N --max-steps=N --timeout=MS --max-heap=OBJECTS");
+        eprintln!("         --max-string-length=N --allow-natives=NAME,... --no-fs");
+        process::exit(64);
+    }
+
+    if let Some(script) = args.get(1) {
+        let options = Options {
+            args: args[2..].to_vec(),
+            ..options
+        };
+        let result = run_file(script, optimize, &options);
+        if gc_stats {
+            print_gc_stats();
+        }
+
+        match result {
             Ok(_) => process::exit(0),
-            Err(errors) => {
+            Err(RuntimeError::Exit(status)) => process::exit(status),
+            Err(error) => {
                 eprintln!("Error running file:\n");
-                errors.iter().for_each(|e| eprintln!("{}", e));
+                Vec::from(error).iter().for_each(|e| eprintln!("{}", e));
                 process::exit(1);
             }
         };
     }
 
-    run_prompt();
+    run_prompt(optimize, &options);
+    if gc_stats {
+        print_gc_stats();
+    }
+}
+
+fn print_gc_stats() {
+    let stats = gc::stats();
+    eprintln!(
+        "[gc] {} allocated, {} collections, {} freed by collection, {} live",
+        stats.allocated, stats.collections, stats.freed, stats.live
+    );
 }
 
-fn init_globals() -> Environment {
+pub(crate) fn init_globals() -> Environment {
     let globals = Environment::new();
     native::define_native_functions(globals.clone());
     globals
 }
 
-fn run_file(filename: &String) -> Result<(), Vec<String>> {
+fn run_file(filename: &String, optimize: bool, options: &Options) -> Result<(), RuntimeError> {
     let globals = init_globals();
     let contents = fs::read_to_string(filename)
         .map_err(|e| Vec::from([format!("Failed to read file '{}': '{}'", filename, e)]))?;
 
-    run(globals, contents).map(|_| ())
+    run(globals, contents, optimize, options, Streams::default()).map(|_| ())
 }
 
-fn run_prompt() {
+/// Prints the tokens or syntax tree of a script, or of stdin without one, as
+/// S-expressions or JSON. With `-O` the tree is optimised first.
+fn run_dump(flag: &str, filename: Option<&String>, optimize: bool, options: &Options) -> i32 {
+    let (what, format) = flag.split_once('=').unwrap_or((flag, "sexp"));
+
+    if !matches!(what, "--dump-tokens" | "--dump-ast") || !matches!(format, "sexp" | "json") {
+        eprintln!(
+            "Unknown dump '{}', expected --dump-tokens or --dump-ast",
+            flag
+        );
+        eprintln!("optionally followed by =sexp or =json");
+        return 64;
+    }
+
+    let contents = match filename {
+        Some(filename) => fs::read_to_string(filename)
+            .map_err(|e| format!("Failed to read file '{}': '{}'", filename, e)),
+        None => io::read_to_string(io::stdin()).map_err(|e| e.to_string()),
+    };
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    let dump = contents
+        .parse()
+        .and_then(|tokens: Tokens| match (what, format) {
+            ("--dump-tokens", "sexp") => Ok(ast_printer::print_tokens(tokens.iter())),
+            ("--dump-tokens", "json") => Ok(ast_printer::print_tokens_json(tokens.iter())),
+            ("--dump-ast", format) => {
+                let (mut statements, spans) = parser::parse_with_spans(tokens)?;
+                if optimize {
+                    statements = optimizer::optimize(statements, options.max_string_length);
+                }
+
+                match format {
+                    "json" => Ok(ast_printer::print_json(&statements, &spans)),
+                    _ => Ok(ast_printer::print(&statements)),
+                }
+            }
+            _ => unreachable!(),
+        });
+
+    match dump {
+        Ok(dump) => {
+            println!("{}", dump.trim_end());
+            0
+        }
+        Err(errors) => {
+            errors.iter().for_each(|e| eprintln!("{}", e));
+            65
+        }
+    }
+}
+
+/// Formats the given files in place, or with `--check` lists the ones that
+/// aren't formatted. Without files it formats stdin to stdout.
+fn run_fmt(args: &[String]) -> i32 {
+    let check = args.iter().any(|a| a == "--check");
+    let files: Vec<&String> = args.iter().filter(|a| *a != "--check").collect();
+
+    if files.is_empty() {
+        let source = io::read_to_string(io::stdin()).unwrap_or_default();
+
+        return match formatter::format(&source) {
+            Ok(formatted) if check => (formatted != source) as i32,
+            Ok(formatted) => {
+                print!("{}", formatted);
+                0
+            }
+            Err(errors) => {
+                errors.iter().for_each(|e| eprintln!("{}", e));
+                65
+            }
+        };
+    }
+
+    let mut status = 0;
+
+    for file in files {
+        let result = fs::read_to_string(file)
+            .map_err(|e| vec![format!("Failed to read file '{}': '{}'", file, e)])
+            .and_then(|source| Ok((formatter::format(&source)?, source)));
+
+        match result {
+            Ok((formatted, source)) if formatted == source => {}
+            Ok(_) if check => {
+                println!("{} is not formatted", file);
+                status = 1;
+            }
+            Ok((formatted, _)) => {
+                if let Err(e) = fs::write(file, formatted) {
+                    eprintln!("Failed to write file '{}': '{}'", file, e);
+                    status = 1;
+                }
+            }
+            Err(errors) => {
+                eprintln!("Error formatting {}:\n", file);
+                errors.iter().for_each(|e| eprintln!("{}", e));
+                status = 65;
+            }
+        }
+    }
+
+    status
+}
+
+fn run_prompt(optimize: bool, options: &Options) {
     let globals = init_globals();
+    // scripts read from the same stdin as the prompt
+    let streams = Streams::default();
 
-    for line in io::stdin().lines() {
-        match run(globals.clone(), line.unwrap()) {
+    while let Ok(Some(line)) = streams.read_line() {
+        match run(globals.clone(), line, optimize, options, streams.clone()) {
             Ok(_) => {}
+            Err(RuntimeError::Exit(status)) => process::exit(status),
             Err(errors) => {
                 format!("Error running line: {:?}", errors);
             }
@@ -67,10 +327,32 @@ fn run_prompt() {
     }
 }
 
-fn run(globals: Environment, contents: String) -> Result<(), Vec<String>> {
+fn run(
+    globals: Environment,
+    contents: String,
+    optimize: bool,
+    options: &Options,
+    streams: Streams,
+) -> Result<(), RuntimeError> {
     let tokens: Tokens = contents.parse()?;
-    let statements: Vec<Stmt> = parser::parse(tokens)?;
+    let (mut statements, spans) = parser::parse_with_spans(tokens)?;
+
+    if optimize {
+        // resolve the whole program first so errors in code the optimiser
+        // removes are still reported
+        resolver::resolve_locals(&statements)?;
+        statements = optimizer::optimize(statements, options.max_string_length);
+    }
+
+    resolver::private::scope_to_classes(&mut statements);
     let locals = resolver::resolve_locals(&statements)?;
 
-    interpreter::interpret(globals, locals, &statements)
+    interpreter::interpret(
+        globals,
+        locals,
+        spans,
+        &statements,
+        options.clone(),
+        streams,
+    )
 }
@@ -0,0 +1,656 @@
+use std::{
+    cmp::Ordering,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
+use super::{define, Context, NativeError};
+use crate::{
+    environment::Environment,
+    tokens::{Callable, Class, Literal, LoxCallable, LoxInstance, Native},
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// A moment in time and the fixed offset from UTC it's shown in. `DateTime`
+/// instances keep them in their `timestamp` field, in seconds since the Unix
+/// epoch, and their `offset` field, in hours ahead of UTC.
+#[derive(Clone, Copy)]
+struct DateTime {
+    timestamp: Decimal,
+    /// In seconds.
+    offset: i64,
+}
+
+/// The calendar date and time of a `DateTime` at its offset.
+struct Civil {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: i64,
+    minute: i64,
+    second: Decimal,
+    /// From 0 for Monday to 6 for Sunday.
+    weekday: usize,
+    /// From 1 for the 1st of January.
+    day_of_year: i64,
+}
+
+pub(super) fn define_datetime(mut env: Environment) {
+    env.define(
+        "DateTime",
+        Literal::Callable(LoxCallable::new(
+            "DateTime".to_string(),
+            Callable::Class(Box::new(class())),
+        )),
+    );
+    define(&mut env, "sleep", 1, |context, arguments| {
+        let seconds = number(&arguments[0], "sleep() seconds")?;
+        if seconds.is_sign_negative() {
+            return Err(format!("Can't sleep for a negative time, got {}.", seconds).into());
+        }
+
+        match &context.virtual_clock {
+            Some(_) => context.time.set(context.time.get() + seconds),
+            None => {
+                let mut duration = Duration::from_secs_f64(seconds.to_f64().unwrap_or(0.0));
+                // wake up in time for the next statement to time out
+                if let Some(deadline) = context.deadline {
+                    duration = duration.min(deadline.saturating_duration_since(Instant::now()));
+                }
+                thread::sleep(duration)
+            }
+        }
+        Ok(Literal::Nil)
+    });
+}
+
+thread_local! {
+    /// Shared by every DateTime, so those its methods return are still
+    /// `is DateTime`.
+    static CLASS: Class = declare();
+}
+
+fn class() -> Class {
+    CLASS.with(Class::clone)
+}
+
+fn declare() -> Class {
+    Class::native(
+        "DateTime",
+        vec![
+            ("init", Native::new(0, init).with_optional(6)),
+            (
+                "year",
+                Native::new(0, |_, a| field(&a[0], |c| c.year.into())),
+            ),
+            (
+                "month",
+                Native::new(0, |_, a| field(&a[0], |c| c.month.into())),
+            ),
+            ("day", Native::new(0, |_, a| field(&a[0], |c| c.day.into()))),
+            (
+                "hour",
+                Native::new(0, |_, a| field(&a[0], |c| c.hour.into())),
+            ),
+            (
+                "minute",
+                Native::new(0, |_, a| field(&a[0], |c| c.minute.into())),
+            ),
+            ("second", Native::new(0, |_, a| field(&a[0], |c| c.second))),
+            (
+                "weekday",
+                Native::new(0, |_, a| field(&a[0], |c| (c.weekday + 1).into())),
+            ),
+            ("format", Native::new(0, format).with_optional(1)),
+            ("toString", Native::new(0, format)),
+            ("toUtc", Native::new(0, |_, a| with_offset(&a[0], 0))),
+            (
+                "toOffset",
+                Native::new(1, |_, a| with_offset(&a[0], offset(&a[1])?)),
+            ),
+            ("add", Native::new(1, add)),
+            ("addDays", Native::new(1, add_days)),
+            ("addMonths", Native::new(1, add_months)),
+            ("since", Native::new(1, since)),
+            (
+                "compare",
+                Native::new(1, |_, a| {
+                    let ordering = compare(&a[0], &a[1])?;
+                    Ok(Literal::Number(Decimal::from(ordering as i8)))
+                }),
+            ),
+            (
+                "isBefore",
+                Native::new(1, |_, a| {
+                    Ok(Literal::Boolean(compare(&a[0], &a[1])?.is_lt()))
+                }),
+            ),
+            (
+                "isAfter",
+                Native::new(1, |_, a| {
+                    Ok(Literal::Boolean(compare(&a[0], &a[1])?.is_gt()))
+                }),
+            ),
+            (
+                "equals",
+                Native::new(1, |_, a| {
+                    let datetime = this(&a[0])?;
+                    // `==` asks about any value, not only other DateTimes
+                    let equal = other(&a[1]).is_ok_and(|o| o.timestamp == datetime.timestamp);
+                    Ok(Literal::Boolean(equal))
+                }),
+            ),
+        ],
+    )
+}
+
+/// `DateTime()` is now, `DateTime(text)` parses an ISO-8601 date and time and
+/// `DateTime(year, month, day, hour?, minute?, second?)` is a time in UTC.
+fn init(context: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let datetime = match &arguments[1..] {
+        [] => now(context),
+        [Literal::String(text)] => parse(text)?,
+        [year, month, day, time @ ..] => {
+            let whole = |value: Option<&Literal>, what| value.map_or(Ok(0), |v| whole(v, what));
+            from_civil(
+                whole(Some(year), "year")?,
+                whole(Some(month), "month")?,
+                whole(Some(day), "day")?,
+                whole(time.first(), "hour")?,
+                whole(time.get(1), "minute")?,
+                time.get(2)
+                    .map_or(Ok(Decimal::ZERO), |s| number(s, "second"))?,
+                0,
+            )?
+        }
+        _ => {
+            return Err(
+                "DateTime needs an ISO-8601 string, or a year, month and day."
+                    .to_string()
+                    .into(),
+            )
+        }
+    };
+
+    match &arguments[0] {
+        Literal::ClassInstance(instance) => store(&mut instance.clone(), datetime),
+        _ => unreachable!("DateTime methods are bound to their instance"),
+    }
+    Ok(Literal::Nil)
+}
+
+fn now(context: &Context) -> DateTime {
+    let timestamp = match &context.virtual_clock {
+        Some(_) => context.time.get(),
+        None => {
+            let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+            let nanos = now.map_or(0, |d| d.as_nanos() as i128);
+            Decimal::from_i128_with_scale(nanos, 9).normalize()
+        }
+    };
+
+    DateTime {
+        timestamp,
+        offset: 0,
+    }
+}
+
+/// Parses `YYYY-MM-DD`, optionally followed by `THH:MM`, seconds with an
+/// optional fraction and either `Z` or an offset like `+02:00`. Times without
+/// an offset are in UTC.
+fn parse(text: &str) -> Result<DateTime, String> {
+    let invalid = || format!("Invalid ISO-8601 date and time '{}'.", text);
+    let mut chars = Cursor {
+        chars: text.chars().collect(),
+        current: 0,
+    };
+
+    let year = chars.digits(4).ok_or_else(invalid)?;
+    let month = chars
+        .after('-')
+        .and_then(|_| chars.digits(2))
+        .ok_or_else(invalid)?;
+    let day = chars
+        .after('-')
+        .and_then(|_| chars.digits(2))
+        .ok_or_else(invalid)?;
+    let (mut hour, mut minute, mut second) = (0, 0, Decimal::ZERO);
+
+    if chars.after('T').or_else(|| chars.after(' ')).is_some() {
+        hour = chars.digits(2).ok_or_else(invalid)?;
+        minute = chars
+            .after(':')
+            .and_then(|_| chars.digits(2))
+            .ok_or_else(invalid)?;
+
+        if chars.after(':').is_some() {
+            second = Decimal::from(chars.digits(2).ok_or_else(invalid)?);
+
+            if chars.after('.').is_some() {
+                let start = chars.current;
+                while chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    chars.current += 1;
+                }
+                let fraction: String = chars.chars[start..chars.current].iter().collect();
+                let fraction = format!("0.{}", fraction).parse::<Decimal>();
+                second += fraction.map_err(|_| invalid())?;
+            }
+        }
+    }
+
+    let offset = match chars.peek() {
+        None => 0,
+        Some('Z') => {
+            chars.current += 1;
+            0
+        }
+        Some(sign @ ('+' | '-')) => {
+            chars.current += 1;
+            let hours = chars.digits(2).ok_or_else(invalid)?;
+            let minutes = match chars.after(':') {
+                Some(_) => chars.digits(2).ok_or_else(invalid)?,
+                None => chars.digits(2).unwrap_or(0),
+            };
+            let offset = hours * 3600 + minutes * 60;
+            match sign {
+                '-' => -offset,
+                _ => offset,
+            }
+        }
+        Some(_) => return Err(invalid()),
+    };
+
+    match chars.peek() {
+        None => from_civil(year, month, day, hour, minute, second, offset),
+        Some(_) => Err(invalid()),
+    }
+}
+
+struct Cursor {
+    chars: Vec<char>,
+    current: usize,
+}
+
+impl Cursor {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).cloned()
+    }
+
+    fn after(&mut self, expected: char) -> Option<()> {
+        match self.peek() == Some(expected) {
+            true => {
+                self.current += 1;
+                Some(())
+            }
+            false => None,
+        }
+    }
+
+    fn digits(&mut self, count: usize) -> Option<i64> {
+        let digits = self.chars.get(self.current..self.current + count)?;
+        let value = digits.iter().try_fold(0, |value, c| {
+            c.to_digit(10).map(|digit| value * 10 + digit as i64)
+        })?;
+
+        self.current += count;
+        Some(value)
+    }
+}
+
+fn from_civil(
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: Decimal,
+    offset: i64,
+) -> Result<DateTime, String> {
+    if !(1..=9999).contains(&year) {
+        return Err(format!(
+            "Year {} is out of range, it must be from 1 to 9999.",
+            year
+        ));
+    }
+    if !(1..=12).contains(&month) {
+        return Err(format!("Month {} is out of range.", month));
+    }
+    if !(1..=days_in_month(year, month as u32) as i64).contains(&day) {
+        return Err(format!(
+            "Day {} is out of range for {} {}.",
+            day,
+            MONTHS[month as usize - 1],
+            year
+        ));
+    }
+    if !(0..24).contains(&hour) || !(0..60).contains(&minute) {
+        return Err(format!("Time {:02}:{:02} is out of range.", hour, minute));
+    }
+    if second.is_sign_negative() || second >= Decimal::from(60) {
+        return Err(format!("Second {} is out of range.", second));
+    }
+
+    let days = days_from_civil(year, month as u32, day as u32);
+    let local = Decimal::from(days * SECONDS_PER_DAY + hour * 3600 + minute * 60) + second;
+
+    checked(DateTime {
+        timestamp: local - Decimal::from(offset),
+        offset,
+    })
+}
+
+/// Keeps times within the years 1 to 9999, where they can be written as
+/// ISO-8601.
+fn checked(datetime: DateTime) -> Result<DateTime, String> {
+    let local = datetime.timestamp + Decimal::from(datetime.offset);
+    let min = Decimal::from(days_from_civil(1, 1, 1) * SECONDS_PER_DAY);
+    let max = Decimal::from(days_from_civil(10000, 1, 1) * SECONDS_PER_DAY);
+
+    match local >= min && local < max {
+        true => Ok(datetime),
+        false => Err("DateTime is out of range, years must be from 1 to 9999.".to_string()),
+    }
+}
+
+impl DateTime {
+    fn civil(&self) -> Civil {
+        let local = self.timestamp + Decimal::from(self.offset);
+        let days = (local / Decimal::from(SECONDS_PER_DAY)).floor();
+        let seconds = local - days * Decimal::from(SECONDS_PER_DAY);
+        // checked() keeps both small
+        let days = days.to_i64().unwrap();
+        let whole = seconds.trunc().to_i64().unwrap();
+        let (year, month, day) = civil_from_days(days);
+
+        Civil {
+            year,
+            month,
+            day,
+            hour: whole / 3600,
+            minute: whole % 3600 / 60,
+            second: (seconds - Decimal::from(whole / 60 * 60)).normalize(),
+            weekday: (days + 3).rem_euclid(7) as usize,
+            day_of_year: days - days_from_civil(year, 1, 1) + 1,
+        }
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let (era, year_of_era) = (year.div_euclid(400), year.rem_euclid(400));
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let (era, day_of_era) = (days.div_euclid(146_097), days.rem_euclid(146_097));
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month as u32, day as u32)
+}
+
+/// Writes the date and time with `%Y`, `%m`, `%d`, `%H`, `%M`, `%S`, `%z`,
+/// `%a`, `%A`, `%b`, `%B`, `%j`, `%u` and `%%` replaced as in `strftime`, or as
+/// ISO-8601 without a pattern.
+fn format(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let datetime = this(&arguments[0])?;
+    let civil = datetime.civil();
+
+    let pattern = match arguments.get(1) {
+        None | Some(Literal::Nil) => return Ok(Literal::String(iso(&datetime, &civil))),
+        Some(Literal::String(pattern)) => pattern,
+        Some(pattern) => {
+            return Err(format!("DateTime formats must be strings, got {}.", pattern).into())
+        }
+    };
+
+    let mut text = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => text.push_str(&format!("{:04}", civil.year)),
+            Some('m') => text.push_str(&format!("{:02}", civil.month)),
+            Some('d') => text.push_str(&format!("{:02}", civil.day)),
+            Some('H') => text.push_str(&format!("{:02}", civil.hour)),
+            Some('M') => text.push_str(&format!("{:02}", civil.minute)),
+            Some('S') => text.push_str(&format!("{:02}", civil.second.trunc())),
+            Some('z') => text.push_str(&offset_text(datetime.offset)),
+            Some('a') => text.push_str(&WEEKDAYS[civil.weekday][..3]),
+            Some('A') => text.push_str(WEEKDAYS[civil.weekday]),
+            Some('b') => text.push_str(&MONTHS[civil.month as usize - 1][..3]),
+            Some('B') => text.push_str(MONTHS[civil.month as usize - 1]),
+            Some('j') => text.push_str(&format!("{:03}", civil.day_of_year)),
+            Some('u') => text.push_str(&(civil.weekday + 1).to_string()),
+            Some('%') => text.push('%'),
+            Some(c) => return Err(format!("Unknown DateTime format '%{}'.", c).into()),
+            None => return Err("DateTime format ends with '%'.".to_string().into()),
+        }
+    }
+
+    Ok(Literal::String(text))
+}
+
+fn iso(datetime: &DateTime, civil: &Civil) -> String {
+    let second = civil.second.trunc();
+    let fraction = civil.second - second;
+    let fraction = match fraction.is_zero() {
+        true => String::new(),
+        // "0.25" without its "0"
+        false => fraction.to_string()[1..].to_string(),
+    };
+    let offset = match datetime.offset {
+        0 => "Z".to_string(),
+        offset => offset_text(offset),
+    };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{}",
+        civil.year, civil.month, civil.day, civil.hour, civil.minute, second, fraction, offset
+    )
+}
+
+fn offset_text(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.abs() / 60;
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+fn add(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let datetime = this(&arguments[0])?;
+    let seconds = number(&arguments[1], "Seconds")?;
+    moved(datetime, seconds)
+}
+
+fn add_days(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let datetime = this(&arguments[0])?;
+    let days = number(&arguments[1], "Days")?;
+    moved(datetime, days * Decimal::from(SECONDS_PER_DAY))
+}
+
+/// Moves by calendar months, keeping the time of day. Days past the end of the
+/// new month become its last day.
+fn add_months(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let datetime = this(&arguments[0])?;
+    let civil = datetime.civil();
+    let months = whole(&arguments[1], "Months")?;
+
+    let total = (civil.year * 12 + civil.month as i64 - 1)
+        .checked_add(months)
+        .ok_or("DateTime is out of range, years must be from 1 to 9999.".to_string())?;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    if !(1..=9999).contains(&year) {
+        return Err("DateTime is out of range, years must be from 1 to 9999."
+            .to_string()
+            .into());
+    }
+    let day = civil.day.min(days_in_month(year, month));
+
+    let datetime = from_civil(
+        year,
+        month as i64,
+        day as i64,
+        civil.hour,
+        civil.minute,
+        civil.second,
+        datetime.offset,
+    )?;
+    Ok(to_literal(datetime))
+}
+
+fn moved(datetime: DateTime, seconds: Decimal) -> Result<Literal, NativeError> {
+    let timestamp = datetime
+        .timestamp
+        .checked_add(seconds)
+        .ok_or("DateTime is out of range, years must be from 1 to 9999.".to_string())?;
+
+    Ok(to_literal(checked(DateTime {
+        timestamp,
+        ..datetime
+    })?))
+}
+
+/// The seconds from another DateTime to this one.
+fn since(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let (datetime, other) = (this(&arguments[0])?, other(&arguments[1])?);
+    Ok(Literal::Number(datetime.timestamp - other.timestamp))
+}
+
+/// Orders the instants two DateTimes refer to, whatever their offsets.
+fn compare(this_value: &Literal, other_value: &Literal) -> Result<Ordering, String> {
+    let (datetime, other) = (this(this_value)?, other(other_value)?);
+    Ok(datetime.timestamp.cmp(&other.timestamp))
+}
+
+fn with_offset(this_value: &Literal, offset: i64) -> Result<Literal, NativeError> {
+    let datetime = this(this_value)?;
+    Ok(to_literal(checked(DateTime { offset, ..datetime })?))
+}
+
+/// An offset in hours as seconds.
+fn offset(hours: &Literal) -> Result<i64, String> {
+    let seconds = number(hours, "Offset")? * Decimal::from(3600);
+
+    match seconds.fract().is_zero() && seconds.abs() < Decimal::from(SECONDS_PER_DAY) {
+        true if (seconds % Decimal::from(60)).is_zero() => Ok(seconds.to_i64().unwrap()),
+        _ => Err(format!(
+            "Offset must be a whole number of minutes within a day, got {} hours.",
+            hours
+        )),
+    }
+}
+
+fn this(value: &Literal) -> Result<DateTime, String> {
+    match value {
+        Literal::ClassInstance(instance) => load(instance),
+        _ => unreachable!("DateTime methods are bound to their instance"),
+    }
+}
+
+fn other(value: &Literal) -> Result<DateTime, String> {
+    match value {
+        Literal::ClassInstance(instance) => load(instance),
+        value => Err(format!("Expected a DateTime, got {}.", value)),
+    }
+}
+
+fn load(instance: &LoxInstance) -> Result<DateTime, String> {
+    match (instance.get("timestamp"), instance.get("offset")) {
+        (Ok(Literal::Number(timestamp)), Ok(offset_hours)) => checked(DateTime {
+            timestamp,
+            offset: offset(&offset_hours)?,
+        }),
+        _ => {
+            Err("Expected a DateTime, with a number of seconds in its timestamp field.".to_string())
+        }
+    }
+}
+
+fn store(instance: &mut LoxInstance, datetime: DateTime) {
+    let hours = Decimal::from(datetime.offset) / Decimal::from(3600);
+    instance.set("timestamp", Literal::Number(datetime.timestamp));
+    instance.set("offset", Literal::Number(hours.normalize()));
+}
+
+fn to_literal(datetime: DateTime) -> Literal {
+    let mut instance = LoxInstance::new(class());
+    store(&mut instance, datetime);
+    Literal::ClassInstance(instance)
+}
+
+/// Reads one of the calendar fields of the DateTime `this`.
+fn field(this_value: &Literal, get: fn(&Civil) -> Decimal) -> Result<Literal, NativeError> {
+    Ok(Literal::Number(get(&this(this_value)?.civil())))
+}
+
+fn number(value: &Literal, what: &str) -> Result<Decimal, String> {
+    match value {
+        Literal::Number(n) => Ok(*n),
+        value => Err(format!("{} must be a number, got {}.", what, value)),
+    }
+}
+
+fn whole(value: &Literal, what: &str) -> Result<i64, String> {
+    match value {
+        Literal::Number(n) if n.fract().is_zero() => n
+            .to_i64()
+            .ok_or_else(|| format!("{} {} is out of range.", what, n)),
+        value => Err(format!("{} must be a whole number, got {}.", what, value)),
+    }
+}
@@ -0,0 +1,80 @@
+use rust_decimal::Decimal;
+
+use super::{Context, NativeError};
+use crate::tokens::{Class, ClassKind, Literal, LoxInstance, LoxList, Native, Token};
+
+/// The class field holding every variant in order. Scripts can't name it, as
+/// only a class's own methods can use private names.
+const VARIANTS: &str = "#variants";
+
+/// The class an `enum` declaration defines. Its variants are its only
+/// instances, made once and kept in class fields of their own names, each with
+/// a `name` field and an `ordinal` field counting from 0. Scripts can't change
+/// any of these.
+pub(crate) fn class(name: &str, variants: &[Token]) -> Class {
+    let metaclass = Class {
+        kind: ClassKind::Enum,
+        ..Class::native(name, vec![("values", Native::new(0, values))])
+    };
+    let class = Class {
+        statics: Some(LoxInstance::new(metaclass)),
+        kind: ClassKind::Enum,
+        ..Class::native(
+            name,
+            vec![
+                ("init", Native::new(0, init)),
+                ("toString", Native::new(0, to_string)),
+            ],
+        )
+    };
+
+    let mut statics = class.statics.clone().unwrap();
+    let mut instances = Vec::new();
+    for (ordinal, variant) in variants.iter().enumerate() {
+        let mut instance = LoxInstance::new(class.clone());
+        instance.set("name", Literal::String(variant.lexeme.clone()));
+        instance.set("ordinal", Literal::Number(Decimal::from(ordinal)));
+
+        let instance = Literal::ClassInstance(instance);
+        statics.set(&variant.lexeme, instance.clone());
+        instances.push(instance);
+    }
+    statics.set(VARIANTS, Literal::List(LoxList::new(instances)));
+
+    class
+}
+
+fn init(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let name = match &arguments[0] {
+        Literal::ClassInstance(instance) => instance.class_name(),
+        _ => unreachable!("init is bound to a new instance"),
+    };
+    Err(format!("Can't make new variants of enum {}.", name).into())
+}
+
+/// A new list of the variants, so changing it leaves the enum alone.
+fn values(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    match &arguments[0] {
+        Literal::ClassInstance(statics) => match statics.field(VARIANTS) {
+            Some(Literal::List(list)) => Ok(Literal::List(LoxList::new(list.elements()))),
+            _ => Ok(Literal::List(LoxList::new(Vec::new()))),
+        },
+        _ => unreachable!("values is bound to the enum's class fields"),
+    }
+}
+
+fn to_string(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    match &arguments[0] {
+        Literal::ClassInstance(instance) => {
+            let name = instance
+                .field("name")
+                .map_or(String::new(), |n| n.to_string());
+            Ok(Literal::String(format!(
+                "{}.{}",
+                instance.class_name(),
+                name
+            )))
+        }
+        _ => unreachable!("toString is bound to a variant"),
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+};
+
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
+use super::{define, Context, NativeError};
+use crate::{
+    environment::Environment,
+    tokens::{Callable, Class, Literal, LoxCallable, LoxInstance, LoxList, Native, NativeFn},
+};
+
+/// The natives that touch the file system, which `Options::allow_fs` turns
+/// off.
+pub(crate) const NATIVES: &[&str] = &[
+    "appendFile",
+    "exists",
+    "listDir",
+    "mkdir",
+    "openFile",
+    "readFile",
+    "removeFile",
+    "writeFile",
+];
+
+pub(super) fn define_fs(mut env: Environment) {
+    define(&mut env, "readFile", 1, |_, arguments| {
+        let path = path(&arguments[0])?;
+        fs::read_to_string(path)
+            .map(Literal::String)
+            .map_err(|e| error("read file", path, e))
+    });
+    define(&mut env, "writeFile", 2, |_, arguments| {
+        let path = path(&arguments[0])?;
+        fs::write(path, arguments[1].to_string())
+            .map(|_| Literal::Nil)
+            .map_err(|e| error("write file", path, e))
+    });
+    define(&mut env, "appendFile", 2, |_, arguments| {
+        let path = path(&arguments[0])?;
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(arguments[1].to_string().as_bytes()))
+            .map(|_| Literal::Nil)
+            .map_err(|e| error("append to file", path, e))
+    });
+    define(&mut env, "exists", 1, |_, arguments| {
+        let path = path(&arguments[0])?;
+        fs::exists(path)
+            .map(Literal::Boolean)
+            .map_err(|e| error("check", path, e))
+    });
+    define(&mut env, "listDir", 1, |_, arguments| {
+        let path = path(&arguments[0])?;
+        let mut names = fs::read_dir(path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+                    .collect::<Result<Vec<String>, _>>()
+            })
+            .map_err(|e| error("list directory", path, e))?;
+
+        names.sort();
+        Ok(Literal::List(LoxList::new(
+            names.into_iter().map(Literal::String).collect(),
+        )))
+    });
+    define(&mut env, "removeFile", 1, |_, arguments| {
+        let path = path(&arguments[0])?;
+        fs::remove_file(path)
+            .map(|_| Literal::Nil)
+            .map_err(|e| error("remove file", path, e))
+    });
+    define(&mut env, "mkdir", 1, |_, arguments| {
+        let path = path(&arguments[0])?;
+        fs::create_dir_all(path)
+            .map(|_| Literal::Nil)
+            .map_err(|e| error("create directory", path, e))
+    });
+    define(&mut env, "openFile", 1, open_file);
+}
+
+/// Opens a file for reading line by line, returning a `File` object with
+/// `readLine()` and `close()` methods.
+fn open_file(context: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let path = path(&arguments[0])?;
+    let file = File::open(path).map_err(|e| error("open file", path, e))?;
+
+    let mut files = context.files.borrow_mut();
+    let handle = Literal::Number(Decimal::from(files.len()));
+    files.push(Some(Box::new(BufReader::new(file))));
+
+    let mut object = LoxInstance::new(Class::new("File".to_string(), None, BTreeMap::new()));
+    object.set("path", Literal::String(path.to_string()));
+    object.set("readLine", method("readLine", read_line, handle.clone()));
+    object.set("close", method("close", close, handle));
+
+    Ok(Literal::ClassInstance(object))
+}
+
+/// The next line of a file, or nil once it's all been read or it's closed.
+fn read_line(context: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let mut files = context.files.borrow_mut();
+    let handle = handle(&arguments[0]);
+    let file = match files.get_mut(handle) {
+        Some(Some(file)) => file,
+        _ => return Ok(Literal::Nil),
+    };
+
+    let mut line = String::new();
+    match file.read_line(&mut line) {
+        Ok(0) => {
+            // nothing more to read, so let go of the file
+            files[handle] = None;
+            Ok(Literal::Nil)
+        }
+        Ok(_) => Ok(Literal::String(
+            line.trim_end_matches(['\n', '\r']).to_string(),
+        )),
+        Err(e) => Err(format!("Failed to read line: {}", e).into()),
+    }
+}
+
+fn close(context: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    if let Some(file) = context.files.borrow_mut().get_mut(handle(&arguments[0])) {
+        *file = None;
+    }
+    Ok(Literal::Nil)
+}
+
+/// A method of a `File` object, bound to the handle of its file.
+fn method(name: &str, function: NativeFn, handle: Literal) -> Literal {
+    Literal::Callable(LoxCallable::new(
+        format!("File.{}", name),
+        Callable::Native(Native::new(0, function).bind(handle)),
+    ))
+}
+
+fn handle(this: &Literal) -> usize {
+    match this {
+        Literal::Number(n) => n.to_usize().unwrap(),
+        _ => unreachable!("file methods are bound to their handle"),
+    }
+}
+
+fn path(value: &Literal) -> Result<&str, NativeError> {
+    match value {
+        Literal::String(path) => Ok(path),
+        value => Err(format!("Paths must be strings, got {}.", value).into()),
+    }
+}
+
+fn error(action: &str, path: &str, e: std::io::Error) -> NativeError {
+    format!("Failed to {} '{}': {}", action, path, e).into()
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use rust_decimal::prelude::ToPrimitive;
+
+use super::{Context, NativeError};
+use crate::{
+    environment::Environment,
+    json::Json,
+    resolver::private,
+    tokens::{Callable, Class, Literal, LoxCallable, LoxInstance, LoxList, LoxMap, Native},
+};
+
+/// Defines the `json` object, with `json.parse(string)` and
+/// `json.stringify(value, indent?)`.
+pub(super) fn define_json(mut env: Environment) {
+    let mut json = LoxInstance::new(Class::new("json".to_string(), None, BTreeMap::new()));
+    json.set("parse", function("parse", Native::new(1, parse)));
+    json.set(
+        "stringify",
+        function("stringify", Native::new(1, stringify).with_optional(1)),
+    );
+
+    env.define("json", Literal::ClassInstance(json));
+}
+
+fn function(name: &str, native: Native) -> Literal {
+    Literal::Callable(LoxCallable::new(
+        format!("json.{}", name),
+        Callable::Native(native),
+    ))
+}
+
+fn parse(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    match &arguments[0] {
+        Literal::String(s) => match s.parse() {
+            Ok(json) => Ok(from_json(json)),
+            Err(e) => Err(format!("Invalid JSON: {}.", e).into()),
+        },
+        value => Err(format!("Can only parse strings as JSON, got {}.", value).into()),
+    }
+}
+
+fn stringify(_: &Context, arguments: Vec<Literal>) -> Result<Literal, NativeError> {
+    let json = to_json(&arguments[0], &mut Vec::new())?;
+
+    match arguments.get(1) {
+        None | Some(Literal::Nil) => Ok(Literal::String(json.to_string())),
+        Some(Literal::Number(n)) if n.fract().is_zero() && !n.is_sign_negative() => {
+            let indent = n
+                .to_usize()
+                .ok_or("JSON indent is too large.".to_string())?;
+            Ok(Literal::String(json.pretty(indent)))
+        }
+        Some(indent) => Err(format!(
+            "JSON indent must be a whole number of spaces, got {}.",
+            indent
+        )
+        .into()),
+    }
+}
+
+fn from_json(json: Json) -> Literal {
+    match json {
+        Json::Null => Literal::Nil,
+        Json::Bool(b) => Literal::Boolean(b),
+        Json::Number(n) => Literal::Number(n),
+        Json::String(s) => Literal::String(s),
+        Json::Array(items) => {
+            Literal::List(LoxList::new(items.into_iter().map(from_json).collect()))
+        }
+        Json::Object(entries) => Literal::Map(LoxMap::new(
+            entries
+                .into_iter()
+                .map(|(k, v)| (Literal::String(k), from_json(v)))
+                .collect(),
+        )),
+    }
+}
+
+/// Converts a value to JSON. `containers` holds the addresses of the lists,
+/// maps and instances being converted, so a value that contains itself is an
+/// error rather than endless recursion.
+fn to_json(value: &Literal, containers: &mut Vec<usize>) -> Result<Json, String> {
+    let address = match value {
+        Literal::List(list) => Some(list.address()),
+        Literal::Map(map) => Some(map.address()),
+        Literal::ClassInstance(instance) => Some(instance.address()),
+        _ => None,
+    };
+
+    if let Some(address) = address {
+        if containers.contains(&address) {
+            return Err("Can't convert a value that contains itself to JSON.".to_string());
+        }
+        containers.push(address);
+    }
+
+    let json = match value {
+        Literal::Nil => Json::Null,
+        Literal::Boolean(b) => Json::Bool(*b),
+        Literal::Number(n) => Json::Number(*n),
+        Literal::String(s) => Json::String(s.clone()),
+        Literal::List(list) => Json::Array(
+            list.elements()
+                .iter()
+                .map(|element| to_json(element, containers))
+                .collect::<Result<_, _>>()?,
+        ),
+        Literal::Map(map) => Json::Object(
+            map.entries()
+                .iter()
+                .map(|(key, value)| match key {
+                    Literal::String(key) => Ok((key.clone(), to_json(value, containers)?)),
+                    key => Err(format!(
+                        "JSON object keys must be strings, got {}.",
+                        key.repr()
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Literal::ClassInstance(instance) => Json::Object(
+            instance
+                .fields()
+                .iter()
+                .filter(|(name, _)| !private::is_private(name))
+                .map(|(name, value)| Ok((name.clone(), to_json(value, containers)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        Literal::Callable(callable) => {
+            return Err(format!("Can't convert {} to JSON.", callable));
+        }
+    };
+
+    if address.is_some() {
+        containers.pop();
+    }
+    Ok(json)
+}
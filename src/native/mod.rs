@@ -0,0 +1,288 @@
+mod datetime;
+pub(crate) mod enums;
+pub(crate) mod fs;
+mod json;
+mod random;
+
+use std::{
+    cell::{Cell, RefCell},
+    env,
+    io::{self, BufRead, Write},
+    rc::Rc,
+    time::{Instant, SystemTime},
+};
+
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+
+use crate::{
+    environment::Environment,
+    gc,
+    tokens::{Callable, Class, ClassKind, Literal, LoxCallable, LoxList, Native, NativeFn},
+};
+
+use random::Rng;
+
+/// Where `print` and `stderr()` write to and `input()` and `readLine()` read
+/// from. They're shared so the REPL reads its lines from the same input as the
+/// script.
+#[derive(Clone)]
+pub(crate) struct Streams {
+    pub output: Rc<RefCell<dyn Write>>,
+    pub error: Rc<RefCell<dyn Write>>,
+    pub input: Rc<RefCell<dyn BufRead>>,
+}
+
+impl Streams {
+    pub(crate) fn new(
+        output: impl Write + 'static,
+        error: impl Write + 'static,
+        input: impl BufRead + 'static,
+    ) -> Streams {
+        Streams {
+            output: Rc::new(RefCell::new(output)),
+            error: Rc::new(RefCell::new(error)),
+            input: Rc::new(RefCell::new(input)),
+        }
+    }
+
+    /// Reads a line without its line ending, or `None` at the end of the input.
+    pub(crate) fn read_line(&self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+
+        match self.input.borrow_mut().read_line(&mut line)? {
+            0 => Ok(None),
+            _ => Ok(Some(line.trim_end_matches(['\n', '\r']).to_string())),
+        }
+    }
+}
+
+impl Default for Streams {
+    fn default() -> Self {
+        Streams::new(io::stdout(), io::stderr(), io::stdin().lock())
+    }
+}
+
+/// What native functions can reach of the interpreter calling them.
+pub(crate) struct Context {
+    pub streams: Streams,
+    /// The command line arguments after the script.
+    pub args: Vec<String>,
+    /// Files opened with `openFile()`, by handle. Closed ones are `None`.
+    pub files: RefCell<Vec<Option<Box<dyn BufRead>>>>,
+    /// Where `random()` and the other random natives get their numbers.
+    pub rng: RefCell<Rng>,
+    /// What `clock()` reads instead of the system clock, if set.
+    pub virtual_clock: Option<VirtualClock>,
+    /// The virtual time, in seconds.
+    pub time: Cell<Decimal>,
+    /// When the script runs out of time, if it has a timeout. The interpreter
+    /// only checks it between statements, so natives that wait check it too.
+    pub deadline: Option<Instant>,
+}
+
+impl Context {
+    /// Without a `seed` the random natives are seeded from the clock.
+    pub(crate) fn new(
+        streams: Streams,
+        args: Vec<String>,
+        seed: Option<u64>,
+        virtual_clock: Option<VirtualClock>,
+    ) -> Context {
+        Context {
+            streams,
+            args,
+            files: RefCell::new(Vec::new()),
+            rng: RefCell::new(Rng::new(seed)),
+            virtual_clock,
+            time: Cell::new(Decimal::ZERO),
+            deadline: None,
+        }
+    }
+
+    /// Moves the virtual clock on for a statement executed.
+    pub(crate) fn step(&self) {
+        if let Some(clock) = &self.virtual_clock {
+            self.time.set(self.time.get() + clock.per_step);
+        }
+    }
+}
+
+/// A clock for deterministic runs that starts at zero and only moves as the
+/// script reads it and executes statements, so timings print the same every
+/// run.
+#[derive(Clone, Debug)]
+pub(crate) struct VirtualClock {
+    /// Seconds added each time `clock()` is called.
+    pub per_call: Decimal,
+    /// Seconds added for each statement executed.
+    pub per_step: Decimal,
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        VirtualClock {
+            per_call: Decimal::new(1, 3),
+            per_step: Decimal::ZERO,
+        }
+    }
+}
+
+/// Why a native function didn't return a value.
+#[derive(Debug)]
+pub(crate) enum NativeError {
+    Error(String),
+    /// The script called `exit()` with this status.
+    Exit(i32),
+}
+
+impl From<String> for NativeError {
+    fn from(e: String) -> Self {
+        NativeError::Error(e)
+    }
+}
+
+/// The built-in types that `is` checks values other than instances against.
+const TYPES: [&str; 8] = [
+    "Bool", "Class", "Function", "List", "Map", "Nil", "Number", "String",
+];
+
+pub(crate) fn define_native_functions(env: Environment) {
+    define_types(env.clone());
+    define_clock(env.clone());
+    define_gc(env.clone());
+    define_io(env.clone());
+    define_process(env.clone());
+    define_list(env.clone());
+    datetime::define_datetime(env.clone());
+    json::define_json(env.clone());
+    random::define_random(env.clone());
+    fs::define_fs(env)
+}
+
+fn define(env: &mut Environment, name: &str, arity: usize, function: NativeFn) {
+    env.define(
+        name,
+        Literal::Callable(LoxCallable::new(
+            name.to_string(),
+            Callable::Native(Native::new(arity, function)),
+        )),
+    );
+}
+
+fn define_types(mut env: Environment) {
+    for name in TYPES {
+        let init = Native::new(0, |_, _| {
+            Err("Built-in types can only be used with 'is'."
+                .to_string()
+                .into())
+        });
+        env.define(
+            name,
+            Literal::Callable(LoxCallable::new(
+                name.to_string(),
+                Callable::Class(Box::new(Class {
+                    kind: ClassKind::BuiltInType,
+                    ..Class::native(name, vec![("init", init)])
+                })),
+            )),
+        );
+    }
+}
+
+fn define_clock(mut env: Environment) {
+    define(&mut env, "clock", 0, |context, _| {
+        if let Some(clock) = &context.virtual_clock {
+            context.time.set(context.time.get() + clock.per_call);
+            return Ok(Literal::Number(context.time.get()));
+        }
+
+        let now = SystemTime::now();
+        let duration = now.duration_since(SystemTime::UNIX_EPOCH).unwrap();
+
+        Ok(Literal::Number(
+            Decimal::from_f64(duration.as_secs_f64()).unwrap(),
+        ))
+    });
+}
+
+fn define_gc(mut env: Environment) {
+    define(&mut env, "gc", 0, |_, _| {
+        Ok(Literal::Number(Decimal::from(gc::collect())))
+    });
+    define(&mut env, "heapSize", 0, |_, _| {
+        Ok(Literal::Number(Decimal::from(gc::live())))
+    });
+}
+
+fn define_io(mut env: Environment) {
+    define(&mut env, "readLine", 0, |context, _| {
+        read_line(&context.streams)
+    });
+    define(&mut env, "input", 1, |context, arguments| {
+        let mut output = context.streams.output.borrow_mut();
+        write!(output, "{}", arguments[0])
+            .and_then(|_| output.flush())
+            .map_err(|e| format!("Failed to write output: {}", e))?;
+        drop(output);
+
+        read_line(&context.streams)
+    });
+}
+
+fn define_process(mut env: Environment) {
+    define(&mut env, "args", 0, |context, _| {
+        let args = context.args.iter().cloned().map(Literal::String).collect();
+        Ok(Literal::List(LoxList::new(args)))
+    });
+    define(&mut env, "getenv", 1, |_, arguments| match &arguments[0] {
+        Literal::String(name) => Ok(env::var(name).map_or(Literal::Nil, Literal::String)),
+        name => Err(format!("Environment variable names must be strings, got {}.", name).into()),
+    });
+    define(&mut env, "exit", 1, |context, arguments| {
+        let status = match &arguments[0] {
+            Literal::Number(n) if n.fract().is_zero() => n.to_i32(),
+            _ => None,
+        };
+
+        // the host may exit the process straight away
+        _ = context.streams.output.borrow_mut().flush();
+        match status {
+            Some(status) => Err(NativeError::Exit(status)),
+            None => {
+                Err(format!("Exit status must be a whole number, got {}.", arguments[0]).into())
+            }
+        }
+    });
+    define(&mut env, "stderr", 1, |context, arguments| {
+        writeln!(context.streams.error.borrow_mut(), "{}", arguments[0])
+            .map_err(|e| format!("Failed to write to stderr: {}", e))?;
+        Ok(Literal::Nil)
+    });
+}
+
+fn define_list(mut env: Environment) {
+    define(&mut env, "len", 1, |_, arguments| match &arguments[0] {
+        Literal::List(list) => Ok(Literal::Number(Decimal::from(list.len()))),
+        Literal::Map(map) => Ok(Literal::Number(Decimal::from(map.len()))),
+        Literal::String(s) => Ok(Literal::Number(Decimal::from(s.chars().count()))),
+        value => Err(format!("Only lists, maps and strings have a length, got {}.", value).into()),
+    });
+    define(&mut env, "keys", 1, |_, arguments| match &arguments[0] {
+        Literal::Map(map) => Ok(Literal::List(LoxList::new(
+            map.entries().into_iter().map(|(key, _)| key).collect(),
+        ))),
+        value => Err(format!("Only maps have keys, got {}.", value).into()),
+    });
+}
+
+/// The next line of input, or nil at the end of it.
+fn read_line(streams: &Streams) -> Result<Literal, NativeError> {
+    match streams.read_line() {
+        Ok(Some(line)) => Ok(Literal::String(line)),
+        Ok(None) => Ok(Literal::Nil),
+        Err(e) => Err(format!("Failed to read input: {}", e).into()),
+    }
+}
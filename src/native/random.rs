@@ -0,0 +1,124 @@
+use std::time::SystemTime;
+
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
+use super::{define, NativeError};
+use crate::{environment::Environment, tokens::Literal};
+
+/// The digits after the decimal point of the numbers `random()` returns.
+const RANDOM_SCALE: u32 = 16;
+
+/// A small, fast generator (SplitMix64). It's not suitable for cryptography,
+/// but the same seed always produces the same numbers on every platform.
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: Option<u64>) -> Rng {
+        let seed = seed.unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+            now.map_or(0, |d| d.as_nanos() as u64)
+        });
+
+        Rng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `0` up to but not including `n`.
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+pub(super) fn define_random(mut env: Environment) {
+    define(&mut env, "seed", 1, |context, arguments| {
+        // the same seeds as --seed takes
+        let seed = match &arguments[0] {
+            Literal::Number(n) if n.fract().is_zero() => n.to_u64(),
+            _ => None,
+        };
+
+        match seed {
+            Some(seed) => {
+                *context.rng.borrow_mut() = Rng::new(Some(seed));
+                Ok(Literal::Nil)
+            }
+            None => Err(format!(
+                "Seed must be a whole number from 0 to {}, got {}.",
+                u64::MAX,
+                arguments[0]
+            )
+            .into()),
+        }
+    });
+    define(&mut env, "random", 0, |context, _| {
+        let digits = context.rng.borrow_mut().below(10u64.pow(RANDOM_SCALE));
+        Ok(Literal::Number(
+            Decimal::new(digits as i64, RANDOM_SCALE).normalize(),
+        ))
+    });
+    define(&mut env, "randomInt", 2, |context, arguments| {
+        let lo = whole_number("Bounds of randomInt()", &arguments[0])?;
+        let hi = whole_number("Bounds of randomInt()", &arguments[1])?;
+        if lo > hi {
+            return Err(format!("randomInt() needs lo <= hi, got {} and {}.", lo, hi).into());
+        }
+
+        let span = (hi as i128 - lo as i128 + 1) as u64;
+        let offset = match span {
+            // the whole range of i64
+            0 => context.rng.borrow_mut().next_u64(),
+            span => context.rng.borrow_mut().below(span),
+        };
+        Ok(Literal::Number(Decimal::from(
+            lo.wrapping_add(offset as i64),
+        )))
+    });
+    define(
+        &mut env,
+        "shuffle",
+        1,
+        |context, arguments| match &arguments[0] {
+            Literal::List(list) => {
+                let mut rng = context.rng.borrow_mut();
+                for i in (1..list.len()).rev() {
+                    list.swap(i, rng.below(i as u64 + 1) as usize);
+                }
+                Ok(Literal::Nil)
+            }
+            value => Err(format!("Only lists can be shuffled, got {}.", value).into()),
+        },
+    );
+    define(
+        &mut env,
+        "choice",
+        1,
+        |context, arguments| match &arguments[0] {
+            Literal::List(list) => match list.len() {
+                0 => Err("Can't choose from an empty list.".to_string().into()),
+                len => {
+                    let index = context.rng.borrow_mut().below(len as u64) as usize;
+                    Ok(list.get(index).unwrap())
+                }
+            },
+            value => Err(format!("Can only choose from lists, got {}.", value).into()),
+        },
+    );
+}
+
+fn whole_number(what: &str, value: &Literal) -> Result<i64, NativeError> {
+    match value {
+        Literal::Number(n) if n.fract().is_zero() => n
+            .to_i64()
+            .ok_or_else(|| format!("{} must fit in 64 bits, got {}.", what, n).into()),
+        value => Err(format!("{} must be whole numbers, got {}.", what, value).into()),
+    }
+}
@@ -0,0 +1,130 @@
+use rust_decimal::Decimal;
+
+use crate::expr::{self, *};
+use crate::interpreter::evaluate_truthy;
+use crate::stmt::{self, *};
+use crate::tokens::{Literal, TokenType};
+
+use Literal as L;
+use TokenType as TT;
+
+/// Folds expressions whose operands are all literals and drops branches and
+/// loops whose condition is a literal. Anything that would fail at runtime,
+/// like `1 / 0` or `-"a"`, is left for the interpreter to report, and so are
+/// strings longer than `max_string_length`.
+pub(crate) fn optimize(statements: Vec<Stmt>, max_string_length: Option<usize>) -> Vec<Stmt> {
+    let mut optimizer = Optimizer { max_string_length };
+
+    statements
+        .into_iter()
+        .map(|s| stmt::Fold::fold_stmt(&mut optimizer, s))
+        .collect()
+}
+
+struct Optimizer {
+    max_string_length: Option<usize>,
+}
+
+impl Optimizer {
+    fn fits(&self, value: &Literal) -> bool {
+        match (value, self.max_string_length) {
+            (L::String(s), Some(max)) => s.chars().count() <= max,
+            _ => true,
+        }
+    }
+}
+
+impl expr::Fold for Optimizer {
+    fn fold_expr(&mut self, node: Expr) -> Expr {
+        match expr::fold::fold_expr(self, node) {
+            Expr::Logical(e) => logical(e),
+            node => match constant(&node).filter(|value| self.fits(value)) {
+                Some(value) => Expr::Literal(LiteralExpr::new(node.id(), value)),
+                None => node,
+            },
+        }
+    }
+}
+
+impl stmt::Fold for Optimizer {
+    fn fold_stmt(&mut self, node: Stmt) -> Stmt {
+        let node = stmt::fold::fold_stmt(self, node);
+
+        match node {
+            Stmt::If(s) => match literal(&s.condition) {
+                Some(condition) if evaluate_truthy(condition) => *s.then_branch,
+                Some(_) => *s.else_branch,
+                None => Stmt::If(s),
+            },
+            Stmt::While(s) => match literal(&s.condition) {
+                Some(condition) if !evaluate_truthy(condition) => stmt::noop(s.id),
+                _ => Stmt::While(s),
+            },
+            node => node,
+        }
+    }
+}
+
+fn literal(expr: &Expr) -> Option<&Literal> {
+    match expr {
+        Expr::Literal(e) => Some(&e.value),
+        _ => None,
+    }
+}
+
+/// The value of an expression whose operands are literals, if evaluating
+/// it can't fail.
+fn constant(expr: &Expr) -> Option<Literal> {
+    match expr {
+        Expr::Binary(e) => binary(literal(&e.left)?, e.operator.token_type, literal(&e.right)?),
+        Expr::Grouping(e) => literal(&e.expression).cloned(),
+        Expr::Unary(e) => unary(e.operator.token_type, literal(&e.right)?),
+        _ => None,
+    }
+}
+
+/// Replaces `and` and `or` with the operand they evaluate to when the left
+/// one is a literal.
+fn logical(expr: LogicalExpr) -> Expr {
+    let short_circuits = match (literal(&expr.left), expr.operator.token_type) {
+        (Some(left), TT::And) => !evaluate_truthy(left),
+        (Some(left), _) => evaluate_truthy(left),
+        (None, _) => return Expr::Logical(expr),
+    };
+
+    match short_circuits {
+        true => *expr.left,
+        false => *expr.right,
+    }
+}
+
+/// Mirrors `Interpreter::visit_binary`, but gives up instead of failing.
+fn binary(left: &Literal, operator: TokenType, right: &Literal) -> Option<Literal> {
+    match (left, operator, right) {
+        (L::Number(l), TT::Plus, L::Number(r)) => l.checked_add(*r).map(L::Number),
+        (L::Number(l), TT::Minus, L::Number(r)) => l.checked_sub(*r).map(L::Number),
+        (L::Number(l), TT::Slash, L::Number(r)) => l.checked_div(*r).map(L::Number),
+        (L::Number(l), TT::Star, L::Number(r)) => l.checked_mul(*r).map(L::Number),
+
+        (L::String(l), TT::Plus, L::String(r)) => Some(L::String(format!("{}{}", l, r))),
+
+        (L::Number(l), TT::Greater, L::Number(r)) => Some(L::Boolean(l > r)),
+        (L::Number(l), TT::GreaterEqual, L::Number(r)) => Some(L::Boolean(l >= r)),
+        (L::Number(l), TT::Less, L::Number(r)) => Some(L::Boolean(l < r)),
+        (L::Number(l), TT::LessEqual, L::Number(r)) => Some(L::Boolean(l <= r)),
+
+        (l, TT::EqualEqual, r) => Some(L::Boolean(l == r)),
+        (l, TT::BangEqual, r) => Some(L::Boolean(l != r)),
+
+        _ => None,
+    }
+}
+
+/// Mirrors `Interpreter::visit_unary`.
+fn unary(operator: TokenType, right: &Literal) -> Option<Literal> {
+    match (operator, right) {
+        (TT::Bang, v) => Some(L::Boolean(!evaluate_truthy(v))),
+        (TT::Minus, L::Number(n)) => n.checked_mul(Decimal::NEGATIVE_ONE).map(L::Number),
+        _ => None,
+    }
+}
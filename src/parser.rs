@@ -1,59 +1,148 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::stmt::{
-    BlockStmt, ClassStmt, ExpressionStmt, FunctionStmt, IfStmt, PrintStmt, ReturnStmt, Stmt,
-    VarStmt, WhileStmt,
+    BlockStmt, ClassStmt, EnumStmt, ExpressionStmt, FunctionStmt, IfStmt, MatchStmt, PrintStmt,
+    ReturnStmt, Stmt, TraitStmt, VarStmt, WhileStmt,
 };
 use crate::tokens::{Literal, Token, TokenType, Tokens};
 use crate::{expr, expr::*, stmt};
 
-pub(crate) fn parse(tokens: Tokens) -> Result<Vec<Stmt>, Vec<String>> {
-    Parser {
+pub(crate) fn parse_with_spans(tokens: Tokens) -> Result<(Vec<Stmt>, Spans), Vec<String>> {
+    let mut parser = Parser {
         tokens: tokens.into(),
         current_id: 0,
+        line: 1,
+        spans: Spans::new(),
+    };
+
+    let statements = parser.parse()?;
+    Ok((statements, parser.spans))
+}
+
+/// The lines a node covers. Statements start on the line of their first
+/// token, expressions on the line of the last token consumed when they were
+/// built. Both end on the line of their last token.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Spans {
+    lines: HashMap<usize, Span>,
+    /// Nodes the parser made up rather than read: the implicit `nil` of
+    /// `var a;` and `return;`, the empty `else` branch, the missing clauses
+    /// of a `for` and the block a `for` desugars to.
+    synthetic: HashSet<usize>,
+}
+
+impl Spans {
+    fn new() -> Spans {
+        Spans {
+            lines: HashMap::new(),
+            synthetic: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn get(&self, id: usize) -> Option<Span> {
+        self.lines.get(&id).cloned()
+    }
+
+    pub(crate) fn is_synthetic(&self, id: usize) -> bool {
+        self.synthetic.contains(&id)
+    }
+
+    fn record(&mut self, id: usize, line: usize) {
+        self.lines.insert(
+            id,
+            Span {
+                start: line,
+                end: line,
+            },
+        );
+    }
+
+    fn record_start(&mut self, id: usize, line: usize) {
+        if let Some(span) = self.lines.get_mut(&id) {
+            span.start = line;
+        }
     }
-    .parse()
 }
 
 struct Parser {
     tokens: VecDeque<Token>,
     current_id: usize,
+    line: usize,
+    spans: Spans,
 }
 
 impl Parser {
     fn gen_id(&mut self) -> usize {
         let id = self.current_id;
         self.current_id += 1;
+        self.spans.record(id, self.line);
+        id
+    }
+
+    fn gen_synthetic_id(&mut self) -> usize {
+        let id = self.gen_id();
+        self.spans.synthetic.insert(id);
         id
     }
 
+    fn peek_line(&self) -> usize {
+        self.peek().map_or(self.line, |t| t.line_number)
+    }
+
     fn parse(&mut self) -> Result<Vec<Stmt>, Vec<String>> {
         let mut statements: Vec<Stmt> = Vec::new();
 
         while self.peek().is_some() {
-            statements.push(self.declaration()?);
+            let statement = self.declaration().map_err(|errors| {
+                let line = self.peek_line();
+                errors
+                    .iter()
+                    .map(|e| format!("[line {}] {}", line, e))
+                    .collect::<Vec<String>>()
+            })?;
+
+            statements.push(statement);
         }
 
         Ok(statements)
     }
 
     fn declaration(&mut self) -> Result<Stmt, Vec<String>> {
+        let line = self.peek_line();
         let next_token = self.peek().unwrap();
-        match next_token.token_type {
+
+        let statement = match next_token.token_type {
             TokenType::Class => {
                 _ = self.advance();
                 self.class_declaration()
             }
+            TokenType::Enum => {
+                _ = self.advance();
+                self.enum_declaration()
+            }
             TokenType::Fun => {
                 _ = self.advance();
                 self.function("function")
             }
+            TokenType::Trait => {
+                _ = self.advance();
+                self.trait_declaration()
+            }
             TokenType::Var => {
                 _ = self.advance();
                 self.var_declaration()
             }
             _ => self.statement(),
-        }
+        }?;
+
+        self.spans.record_start(statement.id(), line);
+        Ok(statement)
     }
 
     fn class_declaration(&mut self) -> Result<Stmt, Vec<String>> {
@@ -68,13 +157,59 @@ impl Parser {
             _ => None,
         };
 
+        let mut traits: Vec<VariableExpr> = Vec::new();
+        if self.check_one(TokenType::With) {
+            self.advance()?;
+            loop {
+                let identifier = self.consume(TokenType::Identifier, "Expect trait name.")?;
+                traits.push(VariableExpr::new(self.gen_id(), identifier));
+
+                match self.check_one(TokenType::Comma) {
+                    true => self.advance_and_discard()?,
+                    false => break,
+                }
+            }
+        }
+
         self.consume(TokenType::LeftBrace, "Expect '{' before class body")?;
 
         let mut methods: Vec<FunctionStmt> = Vec::new();
+        let mut class_methods: Vec<FunctionStmt> = Vec::new();
+        let mut getters: Vec<FunctionStmt> = Vec::new();
+        let mut setters: Vec<FunctionStmt> = Vec::new();
 
         while self.peek().is_some() && !self.check_one(TokenType::RightBrace) {
-            match self.function("method")? {
-                Stmt::Function(f) => methods.push(f),
+            let line = self.peek_line();
+            // `set` is only a keyword in front of another name
+            let kind = match (self.peek_token_type(), self.peek_next_token_type()) {
+                (TokenType::Class, _) => "class method",
+                (TokenType::Identifier, TokenType::LeftBrace) => "getter",
+                (TokenType::Identifier, TokenType::Identifier)
+                    if self.peek().is_some_and(|t| t.lexeme == "set") =>
+                {
+                    "setter"
+                }
+                _ => "method",
+            };
+            if kind == "class method" || kind == "setter" {
+                self.advance()?;
+            }
+
+            let method = match kind {
+                "getter" => self.getter()?,
+                _ => self.function("method")?,
+            };
+
+            match method {
+                Stmt::Function(f) => {
+                    self.spans.record_start(f.id, line);
+                    match kind {
+                        "class method" => class_methods.push(f),
+                        "getter" => getters.push(f),
+                        "setter" => setters.push(f),
+                        _ => methods.push(f),
+                    }
+                }
                 v => Err(vec![format!(
                     "Expected function to return a function, returned: {:?}",
                     v
@@ -88,7 +223,67 @@ impl Parser {
             self.gen_id(),
             name,
             superclass,
+            traits,
             methods,
+            class_methods,
+            getters,
+            setters,
+        )))
+    }
+
+    fn trait_declaration(&mut self) -> Result<Stmt, Vec<String>> {
+        let name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before trait body")?;
+
+        let mut methods: Vec<FunctionStmt> = Vec::new();
+
+        while self.peek().is_some() && !self.check_one(TokenType::RightBrace) {
+            let line = self.peek_line();
+
+            match self.function("method")? {
+                Stmt::Function(f) => {
+                    self.spans.record_start(f.id, line);
+                    methods.push(f);
+                }
+                v => Err(vec![format!(
+                    "Expected function to return a function, returned: {:?}",
+                    v
+                )])?,
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after trait body.")?;
+
+        Ok(Stmt::Trait(TraitStmt::new(self.gen_id(), name, methods)))
+    }
+
+    fn enum_declaration(&mut self) -> Result<Stmt, Vec<String>> {
+        let name = self.consume(TokenType::Identifier, "Expect enum name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before enum body.")?;
+
+        let mut variants = Vec::new();
+        while !self.check_one(TokenType::RightBrace) {
+            variants.push(self.consume(TokenType::Identifier, "Expect variant name.")?);
+            if !self.check_one(TokenType::RightBrace) {
+                self.consume(TokenType::Comma, "Expect ',' between variants.")?;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after enum body.")?;
+
+        Ok(Stmt::Enum(EnumStmt::new(self.gen_id(), name, variants)))
+    }
+
+    /// A method without a parameter list, run when the property is read.
+    fn getter(&mut self) -> Result<Stmt, Vec<String>> {
+        let name = self.consume(TokenType::Identifier, "Expect getter name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before getter body")?;
+        let body = self.block()?;
+
+        Ok(Stmt::Function(FunctionStmt::new(
+            self.gen_id(),
+            name,
+            Vec::new(),
+            body,
         )))
     }
 
@@ -147,7 +342,7 @@ impl Parser {
                 _ = self.advance();
                 self.expression()?
             }
-            false => Expr::Literal(LiteralExpr::new(self.gen_id(), Literal::Nil)),
+            false => expr::nil(self.gen_synthetic_id()),
         };
 
         self.consume(
@@ -158,7 +353,9 @@ impl Parser {
     }
 
     fn statement(&mut self) -> Result<Stmt, Vec<String>> {
-        match self.peek() {
+        let line = self.peek_line();
+
+        let statement = match self.peek() {
             Some(token) => match token.token_type {
                 TokenType::For => {
                     self.advance()?;
@@ -168,6 +365,10 @@ impl Parser {
                     self.advance()?;
                     self.if_statement()
                 }
+                TokenType::Match => {
+                    self.advance()?;
+                    self.match_statement()
+                }
                 TokenType::Print => {
                     self.advance()?;
                     self.print_statement()
@@ -182,49 +383,67 @@ impl Parser {
                 }
                 TokenType::LeftBrace => {
                     self.advance()?;
-                    Ok(Stmt::Block(BlockStmt::new(self.gen_id(), self.block()?)))
+                    let statements = self.block()?;
+                    Ok(Stmt::Block(BlockStmt::new(self.gen_id(), statements)))
                 }
                 _ => self.expression_statement(),
             },
             None => self.expression_statement(),
-        }
+        }?;
+
+        self.spans.record_start(statement.id(), line);
+        Ok(statement)
     }
 
     fn for_statement(&mut self) -> Result<Stmt, Vec<String>> {
+        let line = self.line;
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
 
         let initializer = match self.peek_token_type() {
             TokenType::Semicolon => {
                 self.advance()?;
-                stmt::noop(self.gen_id())
+                stmt::noop(self.gen_synthetic_id())
             }
             TokenType::Var => {
                 self.advance()?;
                 self.var_declaration()?
             }
-            _ => self.expression_statement()?,
+            _ => {
+                self.clause_start()?;
+                self.expression_statement()?
+            }
         };
 
         let condition = match self.peek_token_type() {
-            TokenType::Semicolon => expr::nil(self.gen_id()),
-            _ => self.expression()?,
+            TokenType::Semicolon => expr::nil(self.gen_synthetic_id()),
+            _ => {
+                self.clause_start()?;
+                self.expression()?
+            }
         };
         self.consume(TokenType::Semicolon, "Expect ';' after 'for' condition.")?;
 
         let increment = match self.peek_token_type() {
-            TokenType::RightParen => expr::nil(self.gen_id()),
-            _ => self.expression()?,
+            TokenType::RightParen => expr::nil(self.gen_synthetic_id()),
+            _ => {
+                self.clause_start()?;
+                self.expression()?
+            }
         };
 
         self.consume(TokenType::RightParen, "Expect ')' after 'for' clauses.")?;
 
         let original_body = self.statement()?;
 
+        // the desugared loop belongs to the line the 'for' is on
+        let while_id = self.gen_id();
+        self.spans.record_start(while_id, line);
+
         #[rustfmt::skip]
-        Ok(Stmt::Block(BlockStmt::new(self.gen_id(), vec![
+        Ok(Stmt::Block(BlockStmt::new(self.gen_synthetic_id(), vec![
             initializer,
             Stmt::While(WhileStmt::new(
-                self.gen_id(),
+                while_id,
                 condition,
                 Stmt::Block(BlockStmt::new(self.gen_id(), vec![
                     original_body,
@@ -234,6 +453,15 @@ impl Parser {
         ])))
     }
 
+    /// A `for` clause can't start with a map literal: `{` there is an error, as
+    /// it is in a Lox without maps, rather than a map that is always truthy.
+    fn clause_start(&self) -> Result<(), Vec<String>> {
+        match self.peek_token_type() {
+            TokenType::LeftBrace => Err(vec!["Expect expression.".to_string()]),
+            _ => Ok(()),
+        }
+    }
+
     fn while_statement(&mut self) -> Result<Stmt, Vec<String>> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
@@ -256,7 +484,7 @@ impl Parser {
                 self.advance()?;
                 self.statement()?
             }
-            _ => stmt::noop(self.gen_id()),
+            _ => stmt::noop(self.gen_synthetic_id()),
         };
 
         Ok(Stmt::If(IfStmt::new(
@@ -267,9 +495,42 @@ impl Parser {
         )))
     }
 
+    fn match_statement(&mut self) -> Result<Stmt, Vec<String>> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'match'.")?;
+        let value = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after match value.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before match arms.")?;
+
+        let (mut patterns, mut arms) = (Vec::new(), Vec::new());
+        while !self.check(&[TokenType::RightBrace, TokenType::Else]) {
+            let name = self.consume(TokenType::Identifier, "Expect enum name.")?;
+            let enum_name = Expr::Variable(VariableExpr::new(self.gen_id(), name));
+            self.consume(TokenType::Dot, "Expect '.' after enum name.")?;
+            let variant = self.consume(TokenType::Identifier, "Expect variant name.")?;
+            patterns.push(GetExpr::new(self.gen_id(), enum_name, variant));
+
+            self.consume(TokenType::Arrow, "Expect '=>' after pattern.")?;
+            arms.push(self.statement()?);
+        }
+
+        if self.check_one(TokenType::Else) {
+            self.advance()?;
+            self.consume(TokenType::Arrow, "Expect '=>' after 'else'.")?;
+            arms.push(self.statement()?);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+
+        Ok(Stmt::Match(MatchStmt::new(
+            self.gen_id(),
+            value,
+            patterns,
+            arms,
+        )))
+    }
+
     fn return_statement(&mut self) -> Result<Stmt, Vec<String>> {
         let value = match self.peek_token_type() {
-            TokenType::Semicolon => expr::nil(self.gen_id()),
+            TokenType::Semicolon => expr::nil(self.gen_synthetic_id()),
             _ => self.expression()?,
         };
         self.consume(TokenType::Semicolon, "Expect ';' return statement value.")?;
@@ -322,6 +583,13 @@ impl Parser {
                     g.name,
                     value,
                 ))),
+                Expr::Index(i) => Ok(Expr::IndexSet(IndexSetExpr::new(
+                    self.gen_id(),
+                    *i.object,
+                    i.bracket,
+                    *i.index,
+                    value,
+                ))),
                 Expr::Variable(v) => {
                     let name = v.name;
                     Ok(Expr::Assign(AssignExpr::new(self.gen_id(), name, value)))
@@ -378,6 +646,7 @@ impl Parser {
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
+            TokenType::Is,
         ]) {
             let operator = self.advance()?;
             let right = self.term()?;
@@ -440,6 +709,12 @@ impl Parser {
                         self.consume(TokenType::Identifier, "Expect property name after '.'")?;
                     expr = Expr::Get(GetExpr::new(self.gen_id(), expr, name))
                 }
+                TokenType::LeftBracket => {
+                    let bracket = self.advance()?;
+                    let index = self.expression()?;
+                    self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                    expr = Expr::Index(IndexExpr::new(self.gen_id(), expr, bracket, index))
+                }
                 _ => break,
             }
         }
@@ -486,6 +761,30 @@ impl Parser {
                 self.consume(TokenType::RightParen, "Expect ')' after expression")?;
                 Expr::Grouping(GroupingExpr::new(id, inner_expression))
             }
+            TokenType::LeftBracket => {
+                let mut elements = Vec::new();
+                while !self.check_one(TokenType::RightBracket) {
+                    elements.push(self.expression()?);
+                    if !self.check_one(TokenType::RightBracket) {
+                        self.consume(TokenType::Comma, "Expect ',' between list elements.")?;
+                    }
+                }
+                self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+                Expr::List(ListExpr::new(id, next_token, elements))
+            }
+            TokenType::LeftBrace => {
+                let (mut keys, mut values) = (Vec::new(), Vec::new());
+                while !self.check_one(TokenType::RightBrace) {
+                    keys.push(self.expression()?);
+                    self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                    values.push(self.expression()?);
+                    if !self.check_one(TokenType::RightBrace) {
+                        self.consume(TokenType::Comma, "Expect ',' between map entries.")?;
+                    }
+                }
+                self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+                Expr::Map(MapExpr::new(id, next_token, keys, values))
+            }
             TokenType::Super => {
                 let keyword = next_token;
                 self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
@@ -528,7 +827,18 @@ impl Parser {
         }
     }
 
+    fn peek_next_token_type(&self) -> TokenType {
+        match self.tokens.get(1) {
+            Some(t) => t.token_type,
+            None => TokenType::None,
+        }
+    }
+
     fn advance(&mut self) -> Result<Token, Vec<String>> {
+        if let Some(token) = self.tokens.front() {
+            self.line = token.line_number;
+        }
+
         match self.tokens.pop_front() {
             None => Err(Vec::from([
                 "Tried to pop_front on empty dequeue".to_string()
@@ -554,6 +864,7 @@ impl Parser {
                     TokenType::None,
                     "<nothing>".to_string(),
                     Literal::Nil,
+                    0,
                     0
                 )),
                 message
@@ -0,0 +1,806 @@
+pub(crate) mod private;
+mod symbols;
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    slice::Iter,
+};
+
+use crate::{
+    expr::{self, *},
+    parser::Spans,
+    stmt::{self, *},
+    tokens::Literal,
+};
+
+pub(crate) use symbols::*;
+
+struct SingleError {
+    message: String,
+    // id of the innermost statement being resolved when the error happened
+    id: Option<usize>,
+}
+
+impl SingleError {
+    fn at(self, id: usize) -> SingleError {
+        SingleError {
+            id: self.id.or(Some(id)),
+            ..self
+        }
+    }
+}
+
+impl From<String> for SingleError {
+    fn from(e: String) -> Self {
+        SingleError {
+            message: e,
+            id: None,
+        }
+    }
+}
+
+impl From<&str> for SingleError {
+    fn from(e: &str) -> Self {
+        e.to_string().into()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Scopes(Vec<HashMap<String, bool>>);
+
+impl Scopes {
+    fn new() -> Scopes {
+        Scopes(Vec::new())
+    }
+
+    fn begin_scope(&mut self) {
+        self.0.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.0.pop();
+    }
+
+    fn declare(&mut self, name: String) {
+        match self.0.last_mut() {
+            None => (),
+            Some(scope) => {
+                scope.insert(name, false);
+            }
+        };
+    }
+
+    fn define(&mut self, name: String) {
+        match self.0.last_mut() {
+            None => (),
+            Some(scope) => {
+                scope.insert(name, true);
+            }
+        }
+    }
+
+    fn force_define(&mut self, name: String) {
+        self.0.last_mut().unwrap().insert(name, true);
+    }
+
+    fn top_contains(&self, name: &str) -> bool {
+        match self.0.last() {
+            None => false,
+            Some(map) => map.contains_key(name),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<bool> {
+        match self.0.last() {
+            None => None,
+            Some(map) => map.get(name).cloned(),
+        }
+    }
+
+    fn iter(&self) -> Iter<HashMap<String, bool>> {
+        self.0.iter()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Locals(HashMap<Expr, usize>);
+impl Locals {
+    fn new() -> Locals {
+        Locals(HashMap::new())
+    }
+
+    pub(crate) fn get(&self, expression: &Expr) -> Option<usize> {
+        self.0.get(expression).map(|i| *i)
+    }
+
+    fn resolve(&mut self, expression: Expr, i: usize) {
+        self.0.insert(expression, i);
+    }
+}
+
+pub(crate) fn resolve_locals(statements: &Vec<Stmt>) -> Result<Locals, Vec<String>> {
+    let resolver = Resolver::new();
+    resolver
+        .resolve(statements)
+        .map_err(prepend_resolver_error)?;
+    Ok(resolver.locals.into_inner())
+}
+
+fn prepend_resolver_error(error: SingleError) -> Vec<String> {
+    vec![format!("Resolver Error: {}", error.message)]
+}
+
+/// Resolves the program for editor tooling, collecting every declaration and
+/// reference. Symbols found before an error are still returned.
+pub(crate) fn resolve_symbols(statements: &Vec<Stmt>, spans: &Spans) -> (Symbols, Vec<String>) {
+    let resolver = Resolver::new();
+
+    let errors = match resolver.resolve(statements) {
+        Ok(_) => Vec::new(),
+        Err(error) => {
+            let line = error.id.and_then(|id| spans.get(id)).map_or(0, |s| s.start);
+            vec![format!("[line {}] Resolver Error: {}", line, error.message)]
+        }
+    };
+
+    let mut symbols = resolver.symbols.into_inner();
+    symbols.finish();
+    (symbols, errors)
+}
+
+const PRIVATE_NAME: &str = "Only properties and methods can have private names.";
+
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Clone, Copy)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+    /// In a class method, where `this` is the class itself.
+    ClassMethod,
+    /// In a trait, where `super` is the superclass of whichever class uses it.
+    Trait,
+}
+
+struct Resolver {
+    locals: RefCell<Locals>,
+    scopes: RefCell<Scopes>,
+    symbols: RefCell<Symbols>,
+    current_function: RefCell<FunctionType>,
+    current_class: RefCell<ClassType>,
+    /// The method names of each trait declared so far, to find conflicts.
+    traits: RefCell<HashMap<String, Vec<String>>>,
+    /// The variant names of each enum declared so far, to check that a
+    /// `match` without an `else` covers them all.
+    enums: RefCell<HashMap<String, Vec<String>>>,
+    /// The name of the class being resolved and the private names it
+    /// declares. `None` outside of classes and inside traits.
+    private: RefCell<Option<(String, HashSet<String>)>>,
+}
+
+impl Resolver {
+    fn new() -> Resolver {
+        Resolver {
+            locals: RefCell::new(Locals::new()),
+            scopes: RefCell::new(Scopes::new()),
+            symbols: RefCell::new(Symbols::new()),
+            current_function: RefCell::new(FunctionType::None),
+            current_class: RefCell::new(ClassType::None),
+            traits: RefCell::new(HashMap::new()),
+            enums: RefCell::new(HashMap::new()),
+            private: RefCell::new(None),
+        }
+    }
+
+    fn begin_scope(&self, owner: usize) {
+        self.scopes.borrow_mut().begin_scope();
+        self.symbols.borrow_mut().begin_scope(owner);
+    }
+
+    fn end_scope(&self) {
+        self.scopes.borrow_mut().end_scope();
+        self.symbols.borrow_mut().end_scope();
+    }
+
+    fn force_define(&self, name: &str) {
+        self.scopes.borrow_mut().force_define(name.to_string());
+    }
+
+    fn declare(&self, name: &str) -> Result<(), SingleError> {
+        if private::is_private(name) {
+            return Err(PRIVATE_NAME.into());
+        }
+        let mut scope = self.scopes.borrow_mut();
+
+        if scope.top_contains(name) {
+            return Err("Already a variable with this name in this scope.".into());
+        }
+
+        scope.declare(name.to_string());
+        Ok(())
+    }
+
+    fn define(&self, name: &str) {
+        self.scopes.borrow_mut().define(name.to_string())
+    }
+
+    fn resolve(&self, statements: &Vec<Stmt>) -> Result<(), SingleError> {
+        for statement in statements {
+            self.resolve_statement(statement)?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve_expression(&self, expression: &Expr) -> Result<(), SingleError> {
+        walk_expr(self, expression)
+    }
+
+    /// Resolves the traits a class uses, reporting any method that two of
+    /// them have when the class doesn't override it.
+    fn resolve_traits(&self, stmt: &ClassStmt) -> Result<(), SingleError> {
+        let mut from: HashMap<&str, &str> = HashMap::new();
+        let traits = self.traits.borrow();
+
+        for (i, t) in stmt.traits.iter().enumerate() {
+            let name = t.name.lexeme.as_str();
+            if name == stmt.name.lexeme {
+                return Err("A class can't use itself as a trait.".into());
+            }
+            if stmt.traits[..i]
+                .iter()
+                .any(|other| other.name.lexeme == name)
+            {
+                return Err(format!("Trait {} is used more than once.", name).into());
+            }
+            self.resolve_expression(&Expr::Variable(t.clone()))?;
+
+            let overridden = |method: &str| {
+                stmt.methods
+                    .iter()
+                    .chain(stmt.getters.iter())
+                    .any(|m| m.name.lexeme == method)
+            };
+            for method in traits.get(name).into_iter().flatten() {
+                if overridden(method) {
+                    continue;
+                }
+                if let Some(other) = from.insert(method, name) {
+                    return Err(format!(
+                        "Class {} gets method '{}' from both {} and {}.",
+                        stmt.name.lexeme, method, other, name
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_function(
+        &self,
+        stmt: &FunctionStmt,
+        function_type: FunctionType,
+    ) -> Result<(), SingleError> {
+        let enclosing_function = self.current_function.replace(function_type);
+        self.begin_scope(stmt.id);
+
+        for param in stmt.params.iter() {
+            self.declare(&param.lexeme)?;
+            self.define(&param.lexeme);
+            self.symbols
+                .borrow_mut()
+                .declare(SymbolKind::Parameter, param);
+        }
+
+        self.resolve(&stmt.body)?;
+        self.end_scope();
+        self.current_function.replace(enclosing_function);
+        Ok(())
+    }
+
+    /// Checks that a private member is only used through `this`, inside the
+    /// class that declares it.
+    fn check_private(&self, object: &Expr, name: &str) -> Result<(), SingleError> {
+        if !private::is_private(name) {
+            return Ok(());
+        }
+
+        match &*self.private.borrow() {
+            None => Err(format!(
+                "Can't use private member '{}' outside of the class that declares it.",
+                private::unscoped(name)
+            )
+            .into()),
+            Some(_) if !matches!(object, Expr::This(_)) => Err(format!(
+                "Private member '{}' can only be used through 'this'.",
+                private::unscoped(name)
+            )
+            .into()),
+            Some((class, names)) if !names.contains(name) => Err(format!(
+                "Class {} doesn't declare private member '{}'.",
+                class,
+                private::unscoped(name)
+            )
+            .into()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Checks that the patterns of a `match` are distinct variants of one
+    /// enum and, without an `else` arm, that they cover all of its variants.
+    fn check_match(&self, stmt: &MatchStmt) -> Result<(), SingleError> {
+        let enums = self.enums.borrow();
+        let mut matched: Option<&str> = None;
+        let mut covered: Vec<&str> = Vec::new();
+
+        for pattern in stmt.patterns.iter() {
+            let name = match pattern.object.as_ref() {
+                Expr::Variable(enum_name) => enum_name.name.lexeme.as_str(),
+                _ => unreachable!("patterns are parsed as an enum name and a variant"),
+            };
+            let variant = pattern.name.lexeme.as_str();
+
+            match enums.get(name) {
+                None => return Err(format!("{} is not an enum.", name).into()),
+                Some(variants) if !variants.iter().any(|v| v == variant) => {
+                    return Err(format!("Enum {} has no variant {}.", name, variant).into());
+                }
+                Some(_) => (),
+            }
+            if matched.is_some_and(|m| m != name) {
+                return Err("A match can only have variants of one enum.".into());
+            }
+            if covered.contains(&variant) {
+                return Err(format!("{}.{} is matched more than once.", name, variant).into());
+            }
+
+            matched = Some(name);
+            covered.push(variant);
+        }
+
+        // a value written as a variant has to be one of the matched enum's
+        if let (Expr::Get(value), Some(name)) = (stmt.value.as_ref(), matched) {
+            match value.object.as_ref() {
+                Expr::Variable(object)
+                    if object.name.lexeme != name && enums.contains_key(&object.name.lexeme) =>
+                {
+                    return Err(format!(
+                        "Can't match {}.{} against variants of {}.",
+                        object.name.lexeme, value.name.lexeme, name
+                    )
+                    .into());
+                }
+                _ => (),
+            }
+        }
+
+        if stmt.arms.len() > stmt.patterns.len() {
+            return Ok(());
+        }
+
+        let name = matched.ok_or("A match needs at least one arm.")?;
+        let missing: Vec<&str> = enums[name]
+            .iter()
+            .map(String::as_str)
+            .filter(|v| !covered.contains(v))
+            .collect();
+
+        match missing.is_empty() {
+            true => Ok(()),
+            false => {
+                Err(format!("Match over {} doesn't cover {}.", name, missing.join(", ")).into())
+            }
+        }
+    }
+
+    fn resolve_local(&self, expression: Expr, name: &str) -> Result<(), SingleError> {
+        let scopes = self.scopes.borrow();
+
+        for (i, scope) in scopes.iter().rev().enumerate() {
+            if scope.contains_key(name) {
+                self.locals.borrow_mut().resolve(expression, i);
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_statement(&self, statement: &Stmt) -> Result<(), SingleError> {
+        walk_stmt(self, statement).map_err(|e| e.at(statement.id()))
+    }
+}
+
+impl stmt::Visitor<Result<(), SingleError>> for Resolver {
+    fn visit_block(&self, stmt: &stmt::BlockStmt) -> Result<(), SingleError> {
+        self.begin_scope(stmt.id);
+        self.resolve(&stmt.statements)?;
+        self.end_scope();
+
+        Ok(())
+    }
+
+    fn visit_class(&self, stmt: &ClassStmt) -> Result<(), SingleError> {
+        self.declare(&stmt.name.lexeme)?;
+        self.define(&stmt.name.lexeme);
+        self.symbols
+            .borrow_mut()
+            .declare(SymbolKind::Class, &stmt.name)
+            .superclass = stmt.superclass.as_ref().map(|s| s.name.clone());
+
+        let enclosing_class = self.current_class.replace(ClassType::Class);
+        let enclosing_private = self.private.replace(Some((
+            stmt.name.lexeme.clone(),
+            private::declared_names(stmt),
+        )));
+
+        if let Some(superclass) = &stmt.superclass {
+            if stmt.name.lexeme == superclass.name.lexeme {
+                return Err("A class can't inherit from itself.".into());
+            }
+
+            self.current_class.replace(ClassType::Subclass);
+            self.resolve_expression(&Expr::Variable(superclass.clone()))?;
+        }
+
+        self.resolve_traits(stmt)?;
+
+        if stmt.superclass.is_some() {
+            self.begin_scope(stmt.id);
+            self.define("super");
+        }
+
+        self.begin_scope(stmt.id);
+        self.force_define("this");
+
+        for method in stmt.methods.iter() {
+            self.symbols.borrow_mut().declare_method(
+                &stmt.name.lexeme,
+                &method.name,
+                &method.params,
+            );
+
+            let function_type = match method.name.lexeme.as_str() {
+                "init" => FunctionType::Initializer,
+                _ => FunctionType::Method,
+            };
+            self.resolve_function(method, function_type)?;
+        }
+
+        for method in stmt.getters.iter().chain(stmt.setters.iter()) {
+            if method.name.lexeme == "init" {
+                return Err("An initializer can't be a getter or setter.".into());
+            }
+            self.symbols.borrow_mut().declare_method(
+                &stmt.name.lexeme,
+                &method.name,
+                &method.params,
+            );
+            self.resolve_function(method, FunctionType::Method)?;
+        }
+        if stmt.setters.iter().any(|setter| setter.params.len() != 1) {
+            return Err("A setter must have exactly one parameter.".into());
+        }
+
+        let class_type = self.current_class.replace(ClassType::ClassMethod);
+        for method in stmt.class_methods.iter() {
+            self.symbols.borrow_mut().declare_method(
+                &stmt.name.lexeme,
+                &method.name,
+                &method.params,
+            );
+            self.resolve_function(method, FunctionType::Method)?;
+        }
+        self.current_class.replace(class_type);
+
+        self.end_scope();
+        if stmt.superclass.is_some() {
+            self.end_scope()
+        }
+
+        self.current_class.replace(enclosing_class);
+        self.private.replace(enclosing_private);
+
+        Ok(())
+    }
+
+    fn visit_enum(&self, stmt: &stmt::EnumStmt) -> Result<(), SingleError> {
+        self.declare(&stmt.name.lexeme)?;
+        self.define(&stmt.name.lexeme);
+        self.symbols
+            .borrow_mut()
+            .declare(SymbolKind::Enum, &stmt.name);
+
+        let mut variants = HashSet::new();
+        for variant in stmt.variants.iter() {
+            let name = &variant.lexeme;
+            if private::is_private(name) {
+                return Err(PRIVATE_NAME.into());
+            }
+            if name == "values" {
+                return Err("An enum variant can't be called 'values'.".into());
+            }
+            if !variants.insert(name) {
+                return Err(format!(
+                    "Enum {} has more than one variant called {}.",
+                    stmt.name.lexeme, name
+                )
+                .into());
+            }
+        }
+
+        self.enums.borrow_mut().insert(
+            stmt.name.lexeme.clone(),
+            stmt.variants.iter().map(|v| v.lexeme.clone()).collect(),
+        );
+        Ok(())
+    }
+
+    fn visit_expression(&self, stmt: &stmt::ExpressionStmt) -> Result<(), SingleError> {
+        self.resolve_expression(&stmt.expression)
+    }
+
+    fn visit_function(&self, stmt: &stmt::FunctionStmt) -> Result<(), SingleError> {
+        self.declare(&stmt.name.lexeme)?;
+        self.define(&stmt.name.lexeme);
+        self.symbols
+            .borrow_mut()
+            .declare(SymbolKind::Function, &stmt.name)
+            .params = stmt.params.clone();
+
+        self.resolve_function(stmt, FunctionType::Function)
+    }
+
+    fn visit_if(&self, stmt: &stmt::IfStmt) -> Result<(), SingleError> {
+        self.resolve_expression(&stmt.condition)?;
+        self.resolve_statement(&stmt.then_branch)?;
+        self.resolve_statement(&stmt.else_branch)?;
+
+        Ok(())
+    }
+
+    fn visit_match(&self, stmt: &stmt::MatchStmt) -> Result<(), SingleError> {
+        self.resolve_expression(&stmt.value)?;
+        self.check_match(stmt)?;
+
+        for pattern in stmt.patterns.iter() {
+            self.resolve_expression(&Expr::Get(pattern.clone()))?;
+        }
+        for arm in stmt.arms.iter() {
+            self.resolve_statement(arm)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_print(&self, stmt: &stmt::PrintStmt) -> Result<(), SingleError> {
+        self.resolve_expression(&stmt.expression)
+    }
+
+    fn visit_return(&self, stmt: &stmt::ReturnStmt) -> Result<(), SingleError> {
+        if let FunctionType::None = *self.current_function.borrow() {
+            return Err("Cannot return from top-level code.".into());
+        }
+
+        if let FunctionType::Initializer = *self.current_function.borrow() {
+            if !is_literal_nil(&stmt.value) {
+                return Err("Cannot return a value from an initializer.".into());
+            }
+        }
+
+        self.resolve_expression(&stmt.value)
+    }
+
+    fn visit_trait(&self, stmt: &stmt::TraitStmt) -> Result<(), SingleError> {
+        self.declare(&stmt.name.lexeme)?;
+        self.define(&stmt.name.lexeme);
+        self.symbols
+            .borrow_mut()
+            .declare(SymbolKind::Trait, &stmt.name);
+
+        let enclosing_class = self.current_class.replace(ClassType::Trait);
+        let enclosing_private = self.private.replace(None);
+
+        // the same scopes as a subclass, as `super` is defined for each class
+        // the trait's methods are copied into
+        self.begin_scope(stmt.id);
+        self.define("super");
+        self.begin_scope(stmt.id);
+        self.force_define("this");
+
+        for method in stmt.methods.iter() {
+            if method.name.lexeme == "init" {
+                return Err("A trait can't have an initializer.".into());
+            }
+            self.symbols.borrow_mut().declare_method(
+                &stmt.name.lexeme,
+                &method.name,
+                &method.params,
+            );
+            self.resolve_function(method, FunctionType::Method)?;
+        }
+
+        self.end_scope();
+        self.end_scope();
+        self.current_class.replace(enclosing_class);
+        self.private.replace(enclosing_private);
+
+        let methods = stmt.methods.iter().map(|m| m.name.lexeme.clone()).collect();
+        self.traits
+            .borrow_mut()
+            .insert(stmt.name.lexeme.clone(), methods);
+
+        Ok(())
+    }
+
+    fn visit_var(&self, stmt: &stmt::VarStmt) -> Result<(), SingleError> {
+        self.declare(&stmt.name.lexeme)?;
+        self.resolve_expression(&stmt.initializer)?;
+        self.define(&stmt.name.lexeme);
+        self.symbols
+            .borrow_mut()
+            .declare(SymbolKind::Variable, &stmt.name);
+
+        Ok(())
+    }
+
+    fn visit_while(&self, stmt: &stmt::WhileStmt) -> Result<(), SingleError> {
+        self.resolve_expression(&stmt.condition)?;
+        self.resolve_statement(&stmt.body)?;
+
+        Ok(())
+    }
+}
+
+fn is_literal_nil(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(literal) => match literal.value {
+            Literal::Nil => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+impl expr::Visitor<Result<(), SingleError>> for Resolver {
+    fn visit_assign(&self, expr: &AssignExpr) -> Result<(), SingleError> {
+        if private::is_private(&expr.name.lexeme) {
+            return Err(PRIVATE_NAME.into());
+        }
+        self.resolve_expression(&expr.value)?;
+        self.symbols.borrow_mut().reference(&expr.name);
+        self.resolve_local(Expr::Assign(expr.clone()), &expr.name.lexeme)?;
+
+        Ok(())
+    }
+
+    fn visit_binary(&self, expr: &BinaryExpr) -> Result<(), SingleError> {
+        self.resolve_expression(&expr.left)?;
+        self.resolve_expression(&expr.right)?;
+
+        Ok(())
+    }
+
+    fn visit_call(&self, expr: &CallExpr) -> Result<(), SingleError> {
+        self.resolve_expression(&expr.callee)?;
+
+        for arg in expr.arguments.iter() {
+            self.resolve_expression(arg)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_get(&self, expr: &GetExpr) -> Result<(), SingleError> {
+        self.check_private(&expr.object, &expr.name.lexeme)?;
+        self.resolve_expression(&expr.object)
+    }
+
+    fn visit_grouping(&self, expr: &GroupingExpr) -> Result<(), SingleError> {
+        self.resolve_expression(&expr.expression)
+    }
+
+    fn visit_index(&self, expr: &IndexExpr) -> Result<(), SingleError> {
+        self.resolve_expression(&expr.object)?;
+        self.resolve_expression(&expr.index)
+    }
+
+    fn visit_index_set(&self, expr: &IndexSetExpr) -> Result<(), SingleError> {
+        self.resolve_expression(&expr.value)?;
+        self.resolve_expression(&expr.object)?;
+        self.resolve_expression(&expr.index)
+    }
+
+    fn visit_list(&self, expr: &ListExpr) -> Result<(), SingleError> {
+        for element in expr.elements.iter() {
+            self.resolve_expression(element)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_map(&self, expr: &MapExpr) -> Result<(), SingleError> {
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            self.resolve_expression(key)?;
+            self.resolve_expression(value)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_literal(&self, _expr: &LiteralExpr) -> Result<(), SingleError> {
+        Ok(())
+    }
+
+    fn visit_logical(&self, expr: &LogicalExpr) -> Result<(), SingleError> {
+        self.resolve_expression(&expr.left)?;
+        self.resolve_expression(&expr.right)?;
+
+        Ok(())
+    }
+
+    fn visit_set(&self, expr: &SetExpr) -> Result<(), SingleError> {
+        self.check_private(&expr.object, &expr.name.lexeme)?;
+        self.resolve_expression(&expr.value)?;
+        self.resolve_expression(&expr.object)?;
+
+        Ok(())
+    }
+
+    fn visit_super(&self, expr: &SuperExpr) -> Result<(), SingleError> {
+        if private::is_private(&expr.method.lexeme) {
+            return Err(format!(
+                "Can't use private member '{}' through 'super'.",
+                expr.method.lexeme
+            )
+            .into());
+        }
+
+        match *self.current_class.borrow() {
+            ClassType::None => Err("Can't use 'super' outside of a class.".into()),
+            ClassType::Class => Err("Can't use 'super' in a class with no superclass.".into()),
+            ClassType::ClassMethod => Err("Can't use 'super' in a class method.".into()),
+            _ => self.resolve_local(Expr::Super(expr.clone()), &expr.keyword.lexeme),
+        }
+    }
+
+    fn visit_this(&self, expr: &ThisExpr) -> Result<(), SingleError> {
+        if let ClassType::None = *self.current_class.borrow() {
+            return Err("Can't use 'this' outside of a class.".into());
+        }
+
+        self.resolve_local(
+            Expr::Variable(VariableExpr::new(expr.id, expr.keyword.clone())),
+            &expr.keyword.lexeme,
+        )
+    }
+
+    fn visit_unary(&self, expr: &UnaryExpr) -> Result<(), SingleError> {
+        self.resolve_expression(&expr.right)
+    }
+
+    fn visit_variable(&self, expr: &VariableExpr) -> Result<(), SingleError> {
+        let name = &expr.name.lexeme;
+        if private::is_private(name) {
+            return Err(PRIVATE_NAME.into());
+        }
+        match self.scopes.borrow().get(name) {
+            Some(v) if v == false => {
+                return Err("Can't read local variable in its own initializer.".into());
+            }
+            _ => (),
+        }
+
+        self.symbols.borrow_mut().reference(&expr.name);
+        self.resolve_local(Expr::Variable(expr.clone()), &expr.name.lexeme)
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    expr::{self, *},
+    stmt::{self, *},
+    tokens::Token,
+};
+
+/// Whether a property or method name is private to its class, as in `#name`.
+pub(crate) fn is_private(name: &str) -> bool {
+    name.starts_with('#')
+}
+
+/// Numbers every class declaration renamed, across all the programs run, so
+/// classes declared on different lines of the prompt are told apart too.
+static CLASSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Renames the private members of each class, and their uses through `this`,
+/// to names that also hold a number for the class. A subclass's `#x` is then
+/// a different field from its superclass's, though both are on one instance.
+pub(crate) fn scope_to_classes(statements: &mut [Stmt]) {
+    let mut renamer = Renamer(Vec::new());
+
+    for statement in statements.iter_mut() {
+        stmt::MutVisitor::visit_stmt_mut(&mut renamer, statement);
+    }
+}
+
+/// A private name as it was written, without the class `scope_to_classes`
+/// added to it.
+pub(crate) fn unscoped(name: &str) -> &str {
+    name.split_once('@').map_or(name, |(name, _)| name)
+}
+
+/// The private names a class declares: its `#name` methods and the fields its
+/// methods set with `this.#name = value`.
+pub(super) fn declared_names(class: &ClassStmt) -> HashSet<String> {
+    let mut collector = Collector(HashSet::new());
+
+    let members = class
+        .methods
+        .iter()
+        .chain(class.class_methods.iter())
+        .chain(class.getters.iter())
+        .chain(class.setters.iter());
+    for member in members {
+        if is_private(&member.name.lexeme) {
+            collector.0.insert(member.name.lexeme.clone());
+        }
+        stmt::Walker::walk_function_stmt(&mut collector, member);
+    }
+
+    collector.0
+}
+
+/// The numbers of the classes around the node being renamed, innermost last,
+/// with `None` for a trait, which can't have private members.
+struct Renamer(Vec<Option<usize>>);
+
+impl Renamer {
+    fn rename(&self, name: &mut Token) {
+        if let (Some(Some(class)), true) = (self.0.last(), is_private(&name.lexeme)) {
+            name.lexeme = format!("{}@{}", name.lexeme, class);
+        }
+    }
+}
+
+impl expr::MutVisitor for Renamer {
+    fn visit_get_expr_mut(&mut self, node: &mut GetExpr) {
+        if matches!(*node.object, Expr::This(_)) {
+            self.rename(&mut node.name);
+        }
+        expr::mut_visitor::visit_get_expr_mut(self, node);
+    }
+
+    fn visit_set_expr_mut(&mut self, node: &mut SetExpr) {
+        if matches!(*node.object, Expr::This(_)) {
+            self.rename(&mut node.name);
+        }
+        expr::mut_visitor::visit_set_expr_mut(self, node);
+    }
+}
+
+impl stmt::MutVisitor for Renamer {
+    fn visit_class_stmt_mut(&mut self, node: &mut ClassStmt) {
+        self.0.push(Some(CLASSES.fetch_add(1, Ordering::Relaxed)));
+
+        let members = node
+            .methods
+            .iter_mut()
+            .chain(node.class_methods.iter_mut())
+            .chain(node.getters.iter_mut())
+            .chain(node.setters.iter_mut());
+        for member in members {
+            self.rename(&mut member.name);
+        }
+        stmt::mut_visitor::visit_class_stmt_mut(self, node);
+
+        self.0.pop();
+    }
+
+    fn visit_trait_stmt_mut(&mut self, node: &mut TraitStmt) {
+        self.0.push(None);
+        stmt::mut_visitor::visit_trait_stmt_mut(self, node);
+        self.0.pop();
+    }
+}
+
+struct Collector(HashSet<String>);
+
+impl expr::Walker for Collector {
+    fn walk_set_expr(&mut self, node: &SetExpr) {
+        if matches!(*node.object, Expr::This(_)) && is_private(&node.name.lexeme) {
+            self.0.insert(node.name.lexeme.clone());
+        }
+        expr::walker::walk_set_expr(self, node);
+    }
+}
+
+impl stmt::Walker for Collector {
+    // `this` in a nested class is one of its instances
+    fn walk_class_stmt(&mut self, _: &ClassStmt) {}
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use crate::tokens::Token;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SymbolKind {
+    Class,
+    Enum,
+    Function,
+    Method,
+    Parameter,
+    Trait,
+    Variable,
+}
+
+/// A declared name along with every place it is used.
+#[derive(Clone, Debug)]
+pub(crate) struct Symbol {
+    pub kind: SymbolKind,
+    pub declaration: Token,
+    pub params: Vec<Token>,
+    pub superclass: Option<Token>,
+    /// Name of the class or trait a method belongs to.
+    pub container: Option<String>,
+    /// Id of the node whose scope the symbol lives in, `None` for globals.
+    pub scope: Option<usize>,
+    pub references: Vec<Token>,
+}
+
+impl Symbol {
+    fn new(kind: SymbolKind, declaration: &Token, scope: Option<usize>) -> Symbol {
+        Symbol {
+            kind,
+            declaration: declaration.clone(),
+            params: Vec::new(),
+            superclass: None,
+            container: None,
+            scope,
+            references: Vec::new(),
+        }
+    }
+}
+
+/// Declarations and references collected by the resolver, for editor
+/// tooling rather than for running code.
+#[derive(Debug, Default)]
+pub(crate) struct Symbols {
+    symbols: Vec<Symbol>,
+    scopes: Vec<(usize, HashMap<String, usize>)>,
+    globals: HashMap<String, usize>,
+    unresolved: Vec<Token>,
+}
+
+impl Symbols {
+    pub(crate) fn new() -> Symbols {
+        Symbols::default()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    pub(crate) fn begin_scope(&mut self, owner: usize) {
+        self.scopes.push((owner, HashMap::new()));
+    }
+
+    pub(crate) fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    pub(crate) fn declare(&mut self, kind: SymbolKind, name: &Token) -> &mut Symbol {
+        let scope = self.scopes.last().map(|(owner, _)| *owner);
+        self.symbols.push(Symbol::new(kind, name, scope));
+        let index = self.symbols.len() - 1;
+
+        match self.scopes.last_mut() {
+            Some((_, names)) => names.insert(name.lexeme.clone(), index),
+            None => self.globals.insert(name.lexeme.clone(), index),
+        };
+
+        &mut self.symbols[index]
+    }
+
+    /// Methods are only reachable through instances, so they are not added to
+    /// any scope.
+    pub(crate) fn declare_method(&mut self, class: &str, name: &Token, params: &[Token]) {
+        let scope = self.scopes.last().map(|(owner, _)| *owner);
+        let mut method = Symbol::new(SymbolKind::Method, name, scope);
+        method.container = Some(class.to_string());
+        method.params = params.to_vec();
+        self.symbols.push(method);
+    }
+
+    pub(crate) fn reference(&mut self, name: &Token) {
+        let local = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|(_, names)| names.get(&name.lexeme).cloned());
+
+        match local {
+            Some(index) => self.symbols[index].references.push(name.clone()),
+            // globals can be declared after the code that uses them
+            None => self.unresolved.push(name.clone()),
+        }
+    }
+
+    /// Links references to globals once every global has been declared.
+    pub(crate) fn finish(&mut self) {
+        for name in std::mem::take(&mut self.unresolved) {
+            if let Some(&index) = self.globals.get(&name.lexeme) {
+                self.symbols[index].references.push(name);
+            }
+        }
+    }
+}
@@ -2,16 +2,42 @@ use crate::stmt::Stmt;
 use std::{
     collections::BTreeMap,
     fmt::{Debug, Display},
+    rc::Rc,
 };
 
 use super::{Literal, LoxInstance, Token};
 use crate::environment::Environment;
+use crate::native::{Context, NativeError};
+
+/// What declared a class, for what only some kinds of class allow.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum ClassKind {
+    /// A `class` declaration or a built-in class.
+    Class,
+    /// An `enum` declaration, whose variants and their fields can't change.
+    Enum,
+    /// A built-in type such as `Number`, which `is` checks values other than
+    /// instances against.
+    BuiltInType,
+}
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) struct Class {
-    pub name: String,
+    pub name: Rc<str>,
     pub superclass: Option<LoxInstance>,
     pub methods: BTreeMap<String, Function>,
+    /// Methods of built-in classes, looked up after `methods`. Shared, as
+    /// every instance has a copy of its class.
+    pub natives: Rc<BTreeMap<String, Native>>,
+    /// Holds the class fields, and the class methods as the methods of its own
+    /// class. Built-in classes don't have any.
+    pub statics: Option<LoxInstance>,
+    /// Methods run instead of setting the field of the same name.
+    pub setters: BTreeMap<String, Function>,
+    /// The traits the class uses, in the order their methods are looked up
+    /// after its own. Each is a copy whose methods see the class's `super`.
+    pub traits: Vec<Class>,
+    pub kind: ClassKind,
 }
 
 impl Class {
@@ -21,9 +47,154 @@ impl Class {
         methods: BTreeMap<String, Function>,
     ) -> Self {
         Self {
-            name,
+            name: name.into(),
             superclass,
             methods,
+            natives: Rc::new(BTreeMap::new()),
+            statics: None,
+            setters: BTreeMap::new(),
+            traits: Vec::new(),
+            kind: ClassKind::Class,
+        }
+    }
+
+    pub(crate) fn with_setters(self, setters: BTreeMap<String, Function>) -> Self {
+        Self { setters, ..self }
+    }
+
+    /// Gives the class the methods of `traits`, with `super` in them bound to
+    /// its superclass.
+    pub(crate) fn with_traits(self, traits: Vec<Class>) -> Self {
+        let superclass = self
+            .superclass
+            .clone()
+            .map_or(Literal::Nil, Literal::ClassInstance);
+        let traits = traits
+            .into_iter()
+            .map(|t| {
+                let methods = t
+                    .methods
+                    .iter()
+                    .map(|(name, method)| {
+                        let mut env = Environment::with_enclosing(method.env.clone());
+                        env.define("super", superclass.clone());
+                        (
+                            name.clone(),
+                            Function {
+                                env,
+                                ..method.clone()
+                            },
+                        )
+                    })
+                    .collect();
+                // still the same trait for `is`
+                Class { methods, ..t }
+            })
+            .collect();
+
+        Self { traits, ..self }
+    }
+
+    /// Whether `other` is this class, not only one that looks the same. Copies
+    /// of a class share its class fields, or its natives if it has none.
+    pub(crate) fn same_as(&self, other: &Class) -> bool {
+        match (&self.statics, &other.statics) {
+            (Some(statics), Some(other_statics)) => statics == other_statics,
+            (None, None) => Rc::ptr_eq(&self.natives, &other.natives),
+            _ => false,
+        }
+    }
+
+    /// A method of the class itself or one of its traits, without looking
+    /// in the superclass.
+    pub(crate) fn own_method(&self, name: &str) -> Option<Function> {
+        self.methods
+            .get(name)
+            .or_else(|| self.traits.iter().find_map(|t| t.methods.get(name)))
+            .cloned()
+    }
+
+    /// Gives the class its class methods, which inherit those of `superclass`.
+    pub(crate) fn with_class_methods(
+        self,
+        methods: BTreeMap<String, Function>,
+        superclass: Option<&Class>,
+    ) -> Self {
+        let inherited = superclass.and_then(|s| s.statics.clone());
+        let metaclass = Class::new(self.name.to_string(), inherited, methods);
+
+        Self {
+            statics: Some(LoxInstance::new(metaclass)),
+            ..self
+        }
+    }
+
+    /// A class field, its own or a superclass's, or a class method bound to
+    /// `this`, the class itself.
+    /// Native class methods, like an enum's `values()`, are bound to the
+    /// instance holding the class fields instead.
+    pub(crate) fn get(&self, name: &str, this: Literal) -> Result<Literal, String> {
+        let undefined = || format!("Undefined property: '{}'", name);
+        let statics = self.statics.as_ref().ok_or_else(undefined)?;
+
+        match (statics.class_field(name), statics.find_method(name)) {
+            (Some(value), _) => Ok(value),
+            (None, Some(method)) => Ok(Literal::Callable(LoxCallable::new(
+                name.to_string(),
+                Callable::Function(method.bind_this(this)),
+            ))),
+            (None, None) => statics
+                .method(name)
+                .map(Literal::Callable)
+                .ok_or_else(undefined),
+        }
+    }
+
+    pub(crate) fn set(&self, name: &str, value: Literal) -> Result<(), String> {
+        match &self.statics {
+            _ if self.kind == ClassKind::Enum => {
+                Err(format!("Can't set fields on enum {}.", self.name))
+            }
+            Some(statics) => {
+                statics.clone().set(name, value);
+                Ok(())
+            }
+            None => Err(format!("Can't add fields to built-in class {}.", self.name)),
+        }
+    }
+
+    /// A built-in class whose methods, including `init`, are all native.
+    pub(crate) fn native(name: &str, natives: Vec<(&str, Native)>) -> Self {
+        Self {
+            natives: Rc::new(
+                natives
+                    .into_iter()
+                    .map(|(name, native)| (name.to_string(), native))
+                    .collect(),
+            ),
+            ..Class::new(name.to_string(), None, BTreeMap::new())
+        }
+    }
+
+    /// How many arguments `init` takes, at least and at most, looking through
+    /// the superclasses if the class doesn't have its own.
+    fn init_arity(&self) -> (usize, usize) {
+        let native_arity = |n: &Native| (n.arity, n.arity + n.optional);
+
+        match (
+            self.methods.get("init"),
+            self.natives.get("init"),
+            &self.superclass,
+        ) {
+            (Some(method), _, _) => (method.params.len(), method.params.len()),
+            (None, Some(native), _) => native_arity(native),
+            (None, None, Some(superclass)) => match superclass.find_method("init") {
+                Some(method) => (method.params.len(), method.params.len()),
+                None => superclass
+                    .find_native("init")
+                    .map_or((0, 0), |n| native_arity(&n)),
+            },
+            (None, None, None) => (0, 0),
         }
     }
 }
@@ -34,6 +205,8 @@ pub(crate) struct Function {
     pub params: Vec<Token>,
     pub env: Environment,
     pub is_initializer: bool,
+    /// Run as soon as it's read as a property, rather than when called.
+    pub is_getter: bool,
 }
 
 impl Function {
@@ -43,6 +216,7 @@ impl Function {
             params,
             env,
             is_initializer: false,
+            is_getter: false,
         }
     }
 
@@ -56,23 +230,75 @@ impl Function {
             params,
             env,
             is_initializer: true,
+            is_getter: false,
+        }
+    }
+
+    pub(crate) fn new_getter(body: Vec<Stmt>, env: Environment) -> Function {
+        Self {
+            is_getter: true,
+            ..Function::new(body, Vec::new(), env)
         }
     }
 
     pub(crate) fn bind(&self, instance: LoxInstance) -> Function {
+        self.bind_this(Literal::ClassInstance(instance))
+    }
+
+    /// Binds `this` to any value, like a class for its class methods.
+    pub(crate) fn bind_this(&self, this: Literal) -> Function {
         let mut env = Environment::with_enclosing(self.env.clone());
-        env.define("this", Literal::ClassInstance(instance));
-        Function::new(self.body.clone(), self.params.clone(), env)
+        env.define("this", this);
+        Function {
+            is_getter: self.is_getter,
+            ..Function::new(self.body.clone(), self.params.clone(), env)
+        }
     }
 }
 
-pub(crate) type Native = fn() -> Literal;
+pub(crate) type NativeFn = fn(&Context, Vec<Literal>) -> Result<Literal, NativeError>;
+
+/// A function built into the interpreter. It's only called with between
+/// `arity` and `arity + optional` arguments, after `this` if it's a method
+/// bound to an object.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct Native {
+    pub arity: usize,
+    pub optional: usize,
+    pub function: NativeFn,
+    pub this: Option<Box<Literal>>,
+}
+
+impl Native {
+    pub(crate) fn new(arity: usize, function: NativeFn) -> Native {
+        Native {
+            arity,
+            optional: 0,
+            function,
+            this: None,
+        }
+    }
+
+    /// Also accepts up to `optional` more arguments after the required ones.
+    pub(crate) fn with_optional(self, optional: usize) -> Native {
+        Native { optional, ..self }
+    }
+
+    pub(crate) fn bind(&self, this: Literal) -> Native {
+        Native {
+            this: Some(Box::new(this)),
+            ..self.clone()
+        }
+    }
+}
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) enum Callable {
-    Class(Class),
+    Class(Box<Class>),
     Function(Function),
     Native(Native),
+    /// Methods for classes to share with `with`. Can't be called.
+    Trait(Box<Class>),
 }
 
 #[derive(Clone, Eq, Hash, PartialEq)]
@@ -88,12 +314,19 @@ impl LoxCallable {
 
     pub fn arity(&self) -> usize {
         match &self.callable {
-            Callable::Class(class) => match class.methods.get("init") {
-                Some(method) => method.params.len(),
-                None => 0,
-            },
+            Callable::Class(class) => class.init_arity().0,
             Callable::Function(f) => f.params.len(),
-            Callable::Native(_) => 0,
+            Callable::Native(n) => n.arity,
+            Callable::Trait(_) => 0,
+        }
+    }
+
+    /// The most arguments it can be called with.
+    pub fn max_arity(&self) -> usize {
+        match &self.callable {
+            Callable::Class(class) => class.init_arity().1,
+            Callable::Native(n) => n.arity + n.optional,
+            _ => self.arity(),
         }
     }
 }
@@ -103,7 +336,8 @@ impl Display for LoxCallable {
         f.write_str(&match self.callable {
             Callable::Class(_) => format!("<class {}>", self.name),
             Callable::Function(_) => format!("<fn {}>", self.name),
-            Callable::Native(_) => todo!("<native-fn {}>", self.name),
+            Callable::Native(_) => "<native fn>".to_string(),
+            Callable::Trait(_) => format!("<trait {}>", self.name),
         })
     }
     // format_args!("<fn {}>", self.name)
@@ -1,50 +1,170 @@
 use std::{cell::RefCell, collections::BTreeMap, fmt::Display, rc::Rc};
 
-use super::{Callable, Class, Function, Literal, LoxCallable};
+use super::{Callable, Class, ClassKind, Function, Literal, LoxCallable, Native};
+use crate::gc::{self, Trace};
+use crate::resolver::private;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Debug)]
 pub(crate) struct Inner {
     class: Class,
     fields: BTreeMap<String, Literal>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// An instance of a class. Two instances are only equal if they're the same
+/// one, unless the interpreter finds an `equals()` method to ask.
+#[derive(Clone, Debug)]
 pub(crate) struct LoxInstance(Rc<RefCell<Inner>>);
 
 impl LoxInstance {
     pub(crate) fn new(class: Class) -> LoxInstance {
-        LoxInstance(Rc::new(RefCell::new(Inner {
+        let inner = Rc::new(RefCell::new(Inner {
             class,
             fields: BTreeMap::new(),
-        })))
+        }));
+        gc::register(Rc::downgrade(&inner) as _);
+        LoxInstance(inner)
+    }
+
+    pub(crate) fn address(&self) -> usize {
+        gc::address(&self.0)
     }
 
     pub(crate) fn get(&self, name: &str) -> Result<Literal, String> {
-        match self.0.borrow().fields.get(name) {
-            Some(value) => Ok(value.clone()),
-            None => match self.find_method(name) {
-                None => Err(format!("Undefined property: '{}'", name)),
-                Some(method) => Ok(Literal::Callable(LoxCallable::new(
-                    name.to_string(),
-                    Callable::Function(method.bind(self.clone())),
-                ))),
-            },
+        match self.field(name) {
+            Some(value) => Ok(value),
+            None => self
+                .method(name)
+                .map(Literal::Callable)
+                .ok_or_else(|| format!("Undefined property: '{}'", private::unscoped(name))),
         }
     }
 
+    /// A method bound to this instance, whether it's written in Lox or
+    /// native, without looking at fields.
+    pub(crate) fn method(&self, name: &str) -> Option<LoxCallable> {
+        match (self.find_method(name), self.find_native(name)) {
+            (Some(method), _) => Some(LoxCallable::new(
+                private::unscoped(name).to_string(),
+                Callable::Function(method.bind(self.clone())),
+            )),
+            (None, Some(native)) => Some(LoxCallable::new(
+                format!("{}.{}", self.0.borrow().class.name, name),
+                Callable::Native(native),
+            )),
+            (None, None) => None,
+        }
+    }
+
+    pub(crate) fn class_name(&self) -> Rc<str> {
+        self.0.borrow().class.name.clone()
+    }
+
+    pub(crate) fn class_kind(&self) -> ClassKind {
+        self.0.borrow().class.kind
+    }
+
+    /// Whether the instance's class is `class` or a subclass of it, or uses
+    /// `class` as a trait.
+    pub(crate) fn is_instance_of(&self, class: &Class) -> bool {
+        let inner = self.0.borrow();
+        let own = &inner.class;
+
+        match &own.superclass {
+            _ if own.same_as(class) || own.traits.iter().any(|t| t.same_as(class)) => true,
+            Some(superclass) => superclass.is_instance_of(class),
+            None => false,
+        }
+    }
+
+    /// A field of the instance holding a class's fields, or else of the one
+    /// holding its superclass's, so subclasses read the fields they inherit.
+    pub(crate) fn class_field(&self, name: &str) -> Option<Literal> {
+        let inner = self.0.borrow();
+
+        match (inner.fields.get(name), &inner.class.superclass) {
+            (Some(value), _) => Some(value.clone()),
+            (None, Some(superclass)) => superclass.class_field(name),
+            (None, None) => None,
+        }
+    }
+
+    /// A field, without looking for methods.
+    pub(crate) fn field(&self, name: &str) -> Option<Literal> {
+        self.0.borrow().fields.get(name).cloned()
+    }
+
     pub(crate) fn set(&mut self, name: &str, value: Literal) {
         self.0.borrow_mut().fields.insert(name.to_string(), value);
     }
 
+    pub(crate) fn fields(&self) -> Vec<(String, Literal)> {
+        self.0
+            .borrow()
+            .fields
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
     pub(crate) fn find_method(&self, name: &str) -> Option<Function> {
         let class = &self.0.borrow().class;
 
-        match (class.methods.get(name).cloned(), &class.superclass) {
+        match (class.own_method(name), &class.superclass) {
             (Some(method), _) => Some(method),
             (None, Some(superclass)) => superclass.find_method(name),
             (None, None) => None,
         }
     }
+
+    pub(crate) fn find_setter(&self, name: &str) -> Option<Function> {
+        let class = &self.0.borrow().class;
+
+        match (class.setters.get(name).cloned(), &class.superclass) {
+            (Some(setter), _) => Some(setter),
+            (None, Some(superclass)) => superclass.find_setter(name),
+            (None, None) => None,
+        }
+    }
+
+    /// A native method of a built-in class, bound to this instance.
+    pub(crate) fn find_native(&self, name: &str) -> Option<Native> {
+        self.native(name)
+            .map(|native| native.bind(Literal::ClassInstance(self.clone())))
+    }
+
+    fn native(&self, name: &str) -> Option<Native> {
+        let class = &self.0.borrow().class;
+
+        match (class.natives.get(name).cloned(), &class.superclass) {
+            (Some(native), _) => Some(native),
+            (None, Some(superclass)) => superclass.native(name),
+            (None, None) => None,
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        gc::release();
+    }
+}
+
+impl Trace for Inner {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        gc::trace_class(&self.class, visit);
+        for value in self.fields.values() {
+            gc::trace_literal(value, visit);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.class.methods.clear();
+        self.class.setters.clear();
+        self.class.traits.clear();
+        self.class.superclass = None;
+        self.class.statics = None;
+        self.fields.clear();
+    }
 }
 
 impl Display for LoxInstance {
@@ -53,11 +173,19 @@ impl Display for LoxInstance {
     }
 }
 
+impl PartialEq for LoxInstance {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for LoxInstance {}
+
 impl std::hash::Hash for LoxInstance {
     fn hash<H>(&self, state: &mut H)
     where
         H: std::hash::Hasher,
     {
-        self.0.borrow().hash(state);
+        self.address().hash(state);
     }
 }
@@ -0,0 +1,93 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use super::Literal;
+use crate::gc::{self, Trace};
+
+#[derive(Debug)]
+pub(crate) struct Inner {
+    elements: Vec<Literal>,
+}
+
+/// A list of values, shared between everything that refers to it. Two lists
+/// are only equal if they're the same list.
+#[derive(Clone, Debug)]
+pub(crate) struct LoxList(Rc<RefCell<Inner>>);
+
+impl LoxList {
+    pub(crate) fn new(elements: Vec<Literal>) -> LoxList {
+        let inner = Rc::new(RefCell::new(Inner { elements }));
+        gc::register(Rc::downgrade(&inner) as _);
+        LoxList(inner)
+    }
+
+    pub(crate) fn address(&self) -> usize {
+        gc::address(&self.0)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.borrow().elements.len()
+    }
+
+    pub(crate) fn elements(&self) -> Vec<Literal> {
+        self.0.borrow().elements.clone()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<Literal> {
+        self.0.borrow().elements.get(index).cloned()
+    }
+
+    pub(crate) fn set(&self, index: usize, value: Literal) -> Result<(), String> {
+        match self.0.borrow_mut().elements.get_mut(index) {
+            Some(element) => {
+                *element = value;
+                Ok(())
+            }
+            None => Err(format!("List index {} out of range.", index)),
+        }
+    }
+
+    pub(crate) fn swap(&self, a: usize, b: usize) {
+        self.0.borrow_mut().elements.swap(a, b);
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        gc::release();
+    }
+}
+
+impl Trace for Inner {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        for element in self.elements.iter() {
+            gc::trace_literal(element, visit);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.elements.clear();
+    }
+}
+
+impl Display for LoxList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Literal::List(self.clone()).repr())
+    }
+}
+
+impl PartialEq for LoxList {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for LoxList {}
+
+impl std::hash::Hash for LoxList {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: std::hash::Hasher,
+    {
+        self.address().hash(state);
+    }
+}
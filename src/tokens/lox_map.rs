@@ -0,0 +1,105 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use super::Literal;
+use crate::gc::{self, Trace};
+
+#[derive(Debug)]
+pub(crate) struct Inner {
+    entries: Vec<(Literal, Literal)>,
+}
+
+/// A map from values to values that keeps its entries in the order their keys
+/// were first added, shared between everything that refers to it. Two maps are
+/// only equal if they're the same map.
+#[derive(Clone, Debug)]
+pub(crate) struct LoxMap(Rc<RefCell<Inner>>);
+
+impl LoxMap {
+    pub(crate) fn new(entries: Vec<(Literal, Literal)>) -> LoxMap {
+        let map = LoxMap::allocate();
+        for (key, value) in entries {
+            map.set(key, value);
+        }
+        map
+    }
+
+    fn allocate() -> LoxMap {
+        let inner = Rc::new(RefCell::new(Inner {
+            entries: Vec::new(),
+        }));
+        gc::register(Rc::downgrade(&inner) as _);
+        LoxMap(inner)
+    }
+
+    pub(crate) fn address(&self) -> usize {
+        gc::address(&self.0)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.borrow().entries.len()
+    }
+
+    pub(crate) fn get(&self, key: &Literal) -> Option<Literal> {
+        self.0
+            .borrow()
+            .entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    }
+
+    pub(crate) fn set(&self, key: Literal, value: Literal) {
+        let mut inner = self.0.borrow_mut();
+
+        match inner.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => inner.entries.push((key, value)),
+        }
+    }
+
+    pub(crate) fn entries(&self) -> Vec<(Literal, Literal)> {
+        self.0.borrow().entries.clone()
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        gc::release();
+    }
+}
+
+impl Trace for Inner {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        for (key, value) in self.entries.iter() {
+            gc::trace_literal(key, visit);
+            gc::trace_literal(value, visit);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Display for LoxMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Literal::Map(self.clone()).repr())
+    }
+}
+
+impl PartialEq for LoxMap {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for LoxMap {}
+
+impl std::hash::Hash for LoxMap {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: std::hash::Hasher,
+    {
+        self.address().hash(state);
+    }
+}
@@ -1,12 +1,17 @@
 mod lox_callable;
 mod lox_instance;
+mod lox_list;
+mod lox_map;
 mod scanner;
 
-use std::{collections::VecDeque, fmt::Display, str::FromStr};
+use std::{cell::RefCell, collections::VecDeque, convert::Infallible, fmt::Display, str::FromStr};
 
+pub(crate) use self::scanner::keywords;
 use self::scanner::Scanner;
 pub(crate) use lox_callable::*;
 pub(crate) use lox_instance::*;
+pub(crate) use lox_list::*;
+pub(crate) use lox_map::*;
 use rust_decimal::Decimal;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -15,6 +20,9 @@ pub(crate) enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
     Comma,
     Dot,
     Minus,
@@ -32,6 +40,7 @@ pub(crate) enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    Arrow,
 
     // Literals
     Identifier,
@@ -42,19 +51,24 @@ pub(crate) enum TokenType {
     And,
     Class,
     Else,
+    Enum,
     False,
     Fun,
     For,
     If,
+    Is,
+    Match,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Trait,
     True,
     Var,
     While,
+    With,
 
     Eof,
     None,
@@ -66,10 +80,81 @@ pub(crate) enum Literal {
     Boolean(bool),
     Callable(LoxCallable),
     ClassInstance(LoxInstance),
+    List(LoxList),
+    Map(LoxMap),
     Number(Decimal),
     String(String),
 }
 
+impl Literal {
+    /// How the value is written inside a list or map, where strings are
+    /// quoted so they can be told apart.
+    pub(crate) fn repr(&self) -> String {
+        let repr = self.write(&RefCell::new(Vec::new()), true, &mut |_| {
+            Ok::<_, Infallible>(None)
+        });
+        repr.unwrap_or_else(|never| match never {})
+    }
+
+    /// Writes the value, quoted if it's a string and `quoted`, with the
+    /// elements of lists and maps quoted. `printing` holds the addresses of
+    /// the lists, maps and instances being written around it, which are
+    /// written as `[...]`, `{...}` and `<instance Name>` instead, so a value
+    /// that contains itself ends. `instance` writes an instance, or gives
+    /// `None` for `<instance Name>`, and may write values itself with the
+    /// same `printing`.
+    pub(crate) fn write<E>(
+        &self,
+        printing: &RefCell<Vec<usize>>,
+        quoted: bool,
+        instance: &mut dyn FnMut(&LoxInstance) -> Result<Option<String>, E>,
+    ) -> Result<String, E> {
+        let address = match self {
+            Literal::String(s) if quoted => return Ok(format!("{:?}", s)),
+            Literal::ClassInstance(instance) => instance.address(),
+            Literal::List(list) => list.address(),
+            Literal::Map(map) => map.address(),
+            value => return Ok(value.to_string()),
+        };
+
+        if printing.borrow().contains(&address) {
+            return Ok(match self {
+                Literal::List(_) => "[...]".to_string(),
+                Literal::Map(_) => "{...}".to_string(),
+                value => value.to_string(),
+            });
+        }
+
+        printing.borrow_mut().push(address);
+        let written = match self {
+            Literal::ClassInstance(i) => instance(i).map(|s| s.unwrap_or_else(|| self.to_string())),
+            Literal::List(list) => list
+                .elements()
+                .iter()
+                .map(|element| element.write(printing, true, instance))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|elements| format!("[{}]", elements.join(", "))),
+            Literal::Map(map) => map
+                .entries()
+                .iter()
+                .map(|(key, value)| {
+                    let key = key.write(printing, true, instance)?;
+                    Ok(format!(
+                        "{}: {}",
+                        key,
+                        value.write(printing, true, instance)?
+                    ))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|entries| format!("{{{}}}", entries.join(", "))),
+            _ => unreachable!("only containers and instances have an address"),
+        };
+        printing.borrow_mut().pop();
+
+        written
+    }
+}
+
 impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -77,6 +162,8 @@ impl Display for Literal {
             Literal::Boolean(b) => f.write_fmt(format_args!("{}", b)),
             Literal::Callable(c) => f.write_fmt(format_args!("{}", c)),
             Literal::ClassInstance(c) => f.write_fmt(format_args!("{}", c)),
+            Literal::List(l) => f.write_fmt(format_args!("{}", l)),
+            Literal::Map(m) => f.write_fmt(format_args!("{}", m)),
             Literal::Number(n) => f.write_fmt(format_args!("{}", n)),
             Literal::String(s) => f.write_str(s.as_str()),
         }
@@ -89,6 +176,7 @@ pub(crate) struct Token {
     pub lexeme: String,
     pub literal: Literal,
     pub line_number: usize,
+    pub column: usize,
 }
 
 impl Token {
@@ -97,12 +185,14 @@ impl Token {
         lexeme: String,
         literal: Literal,
         line_number: usize,
+        column: usize,
     ) -> Token {
         Token {
             token_type,
             lexeme,
             literal,
             line_number,
+            column,
         }
     }
 }
@@ -117,15 +207,36 @@ impl Display for Token {
     }
 }
 
-pub(crate) struct Tokens(Vec<Token>);
+/// A `//` comment. Comments aren't tokens, the formatter is the only thing
+/// that needs them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Comment {
+    pub text: String,
+    pub line: usize,
+    /// Whether the comment follows a token on the same line.
+    pub trailing: bool,
+}
+
+pub(crate) struct Tokens(Vec<Token>, Vec<Comment>);
+
+impl Tokens {
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Token> {
+        self.0.iter()
+    }
+
+    pub(crate) fn comments(&self) -> &[Comment] {
+        &self.1
+    }
+}
 
 impl FromStr for Tokens {
     type Err = Vec<String>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let tokens = Scanner::new(s).scan_tokens()?;
+        let mut scanner = Scanner::new(s);
+        let tokens = scanner.scan_tokens()?;
 
-        Ok(Tokens(tokens))
+        Ok(Tokens(tokens, scanner.comments()))
     }
 }
 
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use rust_decimal::Decimal;
 
-use super::{Literal, Token, TokenType};
+use super::{Comment, Literal, Token, TokenType};
 
 lazy_static! {
     static ref KEYWORDS: HashMap<&'static str, TokenType> = {
@@ -10,37 +10,57 @@ lazy_static! {
             ("and", TokenType::And),
             ("class", TokenType::Class),
             ("else", TokenType::Else),
+            ("enum", TokenType::Enum),
             ("false", TokenType::False),
             ("for", TokenType::For),
             ("fun", TokenType::Fun),
             ("if", TokenType::If),
+            ("is", TokenType::Is),
+            ("match", TokenType::Match),
             ("nil", TokenType::Nil),
             ("or", TokenType::Or),
             ("print", TokenType::Print),
             ("return", TokenType::Return),
             ("super", TokenType::Super),
             ("this", TokenType::This),
+            ("trait", TokenType::Trait),
             ("true", TokenType::True),
             ("var", TokenType::Var),
             ("while", TokenType::While),
+            ("with", TokenType::With),
         ])
     };
 }
 
+/// Every reserved word, for editors to offer as completions.
+pub(crate) fn keywords() -> impl Iterator<Item = &'static str> {
+    KEYWORDS.keys().copied()
+}
+
 pub struct Scanner {
-    source: String,
+    /// Indexed by character, so positions and columns count characters rather
+    /// than bytes.
+    source: Vec<char>,
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    column: usize,
+    last_token_line: usize,
+    comments: Vec<Comment>,
 }
 
 impl Scanner {
     pub fn new(source: &str) -> Scanner {
         Scanner {
-            source: source.to_string(),
+            source: source.chars().collect(),
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            column: 1,
+            last_token_line: 0,
+            comments: Vec::new(),
         }
     }
 
@@ -50,13 +70,19 @@ impl Scanner {
 
         while !self.is_at_end() {
             self.start = self.current;
+            self.column = self.start - self.line_start + 1;
             match self.scan_token() {
                 Ok(None) => continue,
-                Ok(token) => tokens.push(token.unwrap()),
-                Err(error) => errors.push(error),
+                Ok(token) => {
+                    self.last_token_line = self.line;
+                    tokens.push(token.unwrap())
+                }
+                Err(error) => errors.push(format!("[line {}] {}", self.line, error)),
             }
         }
 
+        self.start = self.current;
+        self.column = self.start - self.line_start + 1;
         tokens.push(self.new_token(TokenType::Eof, Literal::Nil));
 
         match errors.len() {
@@ -71,6 +97,9 @@ impl Scanner {
             ')' => Ok(Some(self.new_token(TokenType::RightParen, Literal::Nil))),
             '{' => Ok(Some(self.new_token(TokenType::LeftBrace, Literal::Nil))),
             '}' => Ok(Some(self.new_token(TokenType::RightBrace, Literal::Nil))),
+            '[' => Ok(Some(self.new_token(TokenType::LeftBracket, Literal::Nil))),
+            ']' => Ok(Some(self.new_token(TokenType::RightBracket, Literal::Nil))),
+            ':' => Ok(Some(self.new_token(TokenType::Colon, Literal::Nil))),
             ',' => Ok(Some(self.new_token(TokenType::Comma, Literal::Nil))),
             '.' => Ok(Some(self.new_token(TokenType::Dot, Literal::Nil))),
             '-' => Ok(Some(self.new_token(TokenType::Minus, Literal::Nil))),
@@ -89,6 +118,10 @@ impl Scanner {
                     self.advance();
                     Ok(Some(self.new_token(TokenType::EqualEqual, Literal::Nil)))
                 }
+                '>' => {
+                    self.advance();
+                    Ok(Some(self.new_token(TokenType::Arrow, Literal::Nil)))
+                }
                 _ => Ok(Some(self.new_token(TokenType::Equal, Literal::Nil))),
             },
             '<' => match self.peek() {
@@ -111,6 +144,11 @@ impl Scanner {
                     while !self.is_at_end() && self.peek() != '\n' {
                         self.advance();
                     }
+                    self.comments.push(Comment {
+                        text: self.text(self.start, self.current).trim_end().to_string(),
+                        line: self.line,
+                        trailing: self.last_token_line == self.line,
+                    });
                     Ok(None)
                 }
                 _ => Ok(Some(self.new_token(TokenType::Slash, Literal::Nil))),
@@ -119,23 +157,23 @@ impl Scanner {
             '\r' => Ok(None),
             '\t' => Ok(None),
             '\n' => {
-                self.line += 1;
+                self.new_line();
                 Ok(None)
             }
             '"' => self.parse_string(),
             c if self.is_digit(c) => self.parse_number(),
             c if self.is_alpha(c) => self.parse_identifier(),
+            // private names, as in `this.#name`
+            '#' if self.is_alpha(self.peek()) => self.parse_identifier(),
             c => Err(format!("Unexpected charater: {}", c)),
         }
     }
 
     fn parse_string(&mut self) -> Result<Option<Token>, String> {
         while !self.is_at_end() && self.peek() != '"' {
-            if self.peek() == '\n' {
-                self.line += 1;
+            if self.advance() == '\n' {
+                self.new_line();
             }
-
-            self.advance();
         }
 
         if self.is_at_end() {
@@ -146,11 +184,9 @@ impl Scanner {
         self.advance();
 
         // Trim the surrounding quotes
-        let source = self.source.as_str();
-
-        let value = &source[self.start + 1..self.current - 1];
+        let value = self.text(self.start + 1, self.current - 1);
         return Ok(Some(
-            self.new_token(TokenType::String, Literal::String(value.to_string())),
+            self.new_token(TokenType::String, Literal::String(value)),
         ));
     }
 
@@ -168,7 +204,8 @@ impl Scanner {
             }
         }
 
-        let value: Decimal = self.source[self.start..self.current]
+        let value: Decimal = self
+            .text(self.start, self.current)
             .parse()
             .map_err(|e| format!("Failed to parse number: {}", e))?;
 
@@ -182,8 +219,8 @@ impl Scanner {
             self.advance();
         }
 
-        let text: &str = &self.source[self.start..self.current];
-        let token = match KEYWORDS.get(text) {
+        let text = self.text(self.start, self.current);
+        let token = match KEYWORDS.get(text.as_str()) {
             Some(&token_type) => self.new_token(token_type, Literal::Nil),
             None => self.new_token(TokenType::Identifier, Literal::Nil),
         };
@@ -191,6 +228,16 @@ impl Scanner {
         Ok(Some(token))
     }
 
+    /// The `//` comments skipped while scanning, in source order.
+    pub(crate) fn comments(&mut self) -> Vec<Comment> {
+        std::mem::take(&mut self.comments)
+    }
+
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
     fn advance(&mut self) -> char {
         let value = self.peek();
         self.current += 1;
@@ -198,17 +245,25 @@ impl Scanner {
     }
 
     fn peek(&self) -> char {
-        self.source.chars().nth(self.current).unwrap_or('\0')
+        self.source.get(self.current).copied().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        self.source.chars().nth(self.current + 1).unwrap()
+        self.source.get(self.current + 1).copied().unwrap_or('\0')
     }
 
-    fn new_token(&self, token_type: TokenType, literal: Literal) -> Token {
-        let text = &self.source[self.start..self.current];
+    fn text(&self, start: usize, end: usize) -> String {
+        self.source[start..end].iter().collect()
+    }
 
-        Token::new(token_type, text.to_string(), literal, self.line)
+    fn new_token(&self, token_type: TokenType, literal: Literal) -> Token {
+        Token::new(
+            token_type,
+            self.text(self.start, self.current),
+            literal,
+            self.line,
+            self.column,
+        )
     }
 
     fn is_at_end(&self) -> bool {
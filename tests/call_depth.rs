@@ -0,0 +1,61 @@
+// Checks the call depth limit and that calls in tail position don't count
+// towards it.
+
+mod common;
+
+use common::rlox;
+
+#[test]
+fn deep_recursion_is_a_stack_overflow_with_a_backtrace() {
+    let output = rlox(
+        "overflow.lox",
+        &["--max-call-depth=3"],
+        "fun f(n) {\n\
+         \x20 if (n == 0) return 0;\n\
+         \x20 return 1 + f(n - 1);\n\
+         }\n\
+         print f(5);\n",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().ends_with(
+        "Runtime Error: Stack overflow.\n\
+         [line 3] in f()\n\
+         [line 3] in f()\n\
+         [line 3] in f()\n\
+         [line 5] in script\n"
+    ));
+}
+
+#[test]
+fn long_backtraces_are_elided() {
+    let output = rlox(
+        "long_overflow.lox",
+        &["--max-call-depth=100"],
+        "fun f() {\n  f();\n}\nf();\n",
+    );
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.matches("in f()").count(), 20);
+    assert!(stderr.ends_with("... 80 more calls\n[line 4] in script\n"));
+}
+
+#[test]
+fn tail_calls_run_in_constant_stack() {
+    let output = rlox(
+        "tail_calls.lox",
+        &["--max-call-depth=10"],
+        "fun isEven(n) {\n\
+         \x20 if (n == 0) return true;\n\
+         \x20 return isOdd(n - 1);\n\
+         }\n\
+         fun isOdd(n) {\n\
+         \x20 if (n == 0) return false;\n\
+         \x20 return isEven(n - 1);\n\
+         }\n\
+         print isEven(5000);\n",
+    );
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "true\n");
+    assert!(output.status.success());
+}
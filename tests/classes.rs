@@ -0,0 +1,428 @@
+// Checks the class features beyond the book's: class methods and fields,
+// getters and setters, traits, operator overloading, the toString(), equals()
+// and hash() hooks, the is operator and private members.
+
+mod common;
+
+use common::rlox;
+
+#[test]
+fn class_methods_and_fields() {
+    let output = rlox(
+        "class_methods.lox",
+        &[],
+        "class Math {\n\
+         \x20 class square(n) { return n * n; }\n\
+         \x20 class cube(n) { return n * this.square(n); }\n\
+         }\n\
+         print Math.square(3);\n\
+         print Math.cube(3);\n\
+         class Counter {\n\
+         \x20 class make() { this.count = this.count + 1; return this(); }\n\
+         }\n\
+         Counter.count = 0;\n\
+         Counter.make();\n\
+         print Counter.make();\n\
+         print Counter.count;\n\
+         class More < Math {}\n\
+         print More.cube(2);\n\
+         Math.pi = 3;\n\
+         print More.pi;\n\
+         More.pi = 4;\n\
+         print More.pi;\n\
+         print Math.pi;\n\
+         print Math().square;\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "9\n27\n<instance Counter>\n2\n8\n3\n4\n3\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("Runtime Error: Undefined property: 'square'\n"));
+}
+
+#[test]
+fn super_is_not_allowed_in_class_methods() {
+    let output = rlox(
+        "class_methods_super.lox",
+        &[],
+        "class A {}\n\
+         class B < A { class make() { return super.make(); } }\n",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Can't use 'super' in a class method."));
+}
+
+#[test]
+fn getters_and_setters() {
+    let output = rlox(
+        "getters_and_setters.lox",
+        &[],
+        "class Rect {\n\
+         \x20 init(w, h) { this.w = w; this.h = h; }\n\
+         \x20 area { return this.w * this.h; }\n\
+         \x20 width { return this.w; }\n\
+         \x20 set width(value) { this.w = value; }\n\
+         }\n\
+         class Square < Rect {\n\
+         \x20 init(side) { super.init(side, side); }\n\
+         \x20 area { return super.area + 1; }\n\
+         }\n\
+         var rect = Rect(2, 3);\n\
+         print rect.area;\n\
+         rect.width = 10;\n\
+         print rect.width;\n\
+         print rect.area;\n\
+         var square = Square(4);\n\
+         square.width = 5;\n\
+         print square.area;\n\
+         rect.area = 1;\n",
+    );
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "6\n10\n30\n21\n");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Can't set property 'area', which has a getter but no setter."));
+}
+
+#[test]
+fn setters_take_one_parameter() {
+    let output = rlox("setter_parameters.lox", &[], "class A { set x(a, b) {} }\n");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("A setter must have exactly one parameter."));
+}
+
+#[test]
+fn traits() {
+    let output = rlox(
+        "traits.lox",
+        &[],
+        "trait Comparable {\n\
+         \x20 lessThan(other) { return this.compare(other) < 0; }\n\
+         \x20 name() { return \"comparable\"; }\n\
+         }\n\
+         trait Named {\n\
+         \x20 name() { return \"named \" + super.name(); }\n\
+         }\n\
+         class Base {\n\
+         \x20 name() { return \"base\"; }\n\
+         \x20 lessThan(other) { return nil; }\n\
+         }\n\
+         class Number < Base with Named {\n\
+         \x20 init(n) { this.n = n; }\n\
+         \x20 compare(other) { return this.n - other.n; }\n\
+         }\n\
+         class Ordered < Number with Comparable {}\n\
+         print Number(1).name();\n\
+         print Number(1).lessThan(Number(2));\n\
+         print Ordered(1).lessThan(Ordered(2));\n\
+         print Ordered(1).name();\n\
+         print Named;\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "named base\nnil\ntrue\ncomparable\n<trait Named>\n"
+    );
+}
+
+#[test]
+fn trait_conflicts_are_reported() {
+    let output = rlox(
+        "trait_conflicts.lox",
+        &[],
+        "trait A { size() { return 1; } }\n\
+         trait B { size() { return 2; } }\n\
+         class Overrides with A, B { size() { return 3; } }\n\
+         class Conflicts with A, B {}\n",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Class Conflicts gets method 'size' from both A and B."));
+}
+
+#[test]
+fn operator_overloading() {
+    let output = rlox(
+        "operators.lox",
+        &[],
+        "class Vector {\n\
+         \x20 init(x, y) { this.x = x; this.y = y; }\n\
+         \x20 __add__(other) { return Vector(this.x + other.x, this.y + other.y); }\n\
+         \x20 __sub__(other) { return Vector(this.x - other.x, this.y - other.y); }\n\
+         \x20 __mul__(k) { return Vector(this.x * k, this.y * k); }\n\
+         \x20 __neg__() { return Vector(-this.x, -this.y); }\n\
+         \x20 __eq__(other) { return this.x == other.x and this.y == other.y; }\n\
+         \x20 __lt__(other) { return this.x < other.x; }\n\
+         \x20 __index__(i) { if (i == 0) return this.x; return this.y; }\n\
+         }\n\
+         var a = Vector(1, 2);\n\
+         var b = Vector(3, 4);\n\
+         var c = a + b;\n\
+         print c[0];\n\
+         print c[1];\n\
+         print (b - a)[0];\n\
+         print (a * 3)[1];\n\
+         print (-a)[0];\n\
+         print a == Vector(1, 2);\n\
+         print a != Vector(1, 2);\n\
+         print a < b;\n\
+         print a / b;\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "4\n6\n2\n6\n-1\ntrue\nfalse\ntrue\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Unsupported types for binary operation: <instance Vector> / <instance Vector>"));
+}
+
+#[test]
+fn to_string_equals_and_hash() {
+    let output = rlox(
+        "hooks.lox",
+        &[],
+        "class Plain {}\n\
+         class Point {\n\
+         \x20 init(x, y) { this.x = x; this.y = y; }\n\
+         \x20 toString() { return \"point\"; }\n\
+         \x20 equals(other) { return this.x == other.x and this.y == other.y; }\n\
+         \x20 hash() { return this.x + this.y; }\n\
+         }\n\
+         var plain = Plain();\n\
+         print plain == plain;\n\
+         print Plain() == Plain();\n\
+         print Point(1, 2);\n\
+         print [Point(1, 2), \"a\"];\n\
+         print Point(1, 2) == Point(1, 2);\n\
+         print Point(1, 2) != Point(2, 1);\n\
+         var names = {Point(1, 2): \"first\"};\n\
+         names[Point(1, 2)] = \"second\";\n\
+         print len(names);\n\
+         print names[Point(1, 2)];\n\
+         print names[Point(2, 1)];\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "true\nfalse\npoint\n[point, \"a\"]\ntrue\ntrue\n1\nsecond\nnil\n"
+    );
+}
+
+#[test]
+fn prints_values_that_contain_themselves() {
+    let output = rlox(
+        "hooks_cycle.lox",
+        &[],
+        "var m = {\"a\": 1};\n\
+         m[\"self\"] = m;\n\
+         m[\"list\"] = [m];\n\
+         print m;\n\
+         print m == m[\"self\"];\n\
+         print {} == {};\n\
+         class Node {\n\
+         \x20 init() { this.children = [this]; }\n\
+         \x20 toString() { print this; return \"node\"; }\n\
+         }\n\
+         print Node().children;\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"a\": 1, \"self\": {...}, \"list\": [{...}]}\n\
+         true\n\
+         false\n\
+         <instance Node>\n\
+         [node]\n"
+    );
+}
+
+#[test]
+fn is_checks_classes_and_built_in_types() {
+    let output = rlox(
+        "is.lox",
+        &[],
+        "class Animal {}\n\
+         class Dog < Animal {}\n\
+         class Cat < Animal {}\n\
+         var dog = Dog();\n\
+         print dog is Dog;\n\
+         print dog is Animal;\n\
+         print dog is Cat;\n\
+         print Animal() is Dog;\n\
+         print 1 is Number;\n\
+         print \"a\" is String;\n\
+         print false is Bool;\n\
+         print nil is Nil;\n\
+         print clock is Function;\n\
+         print Dog is Class;\n\
+         print dog is Number;\n\
+         print 1 is 2;\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "true\ntrue\nfalse\nfalse\ntrue\ntrue\ntrue\ntrue\ntrue\ntrue\nfalse\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Right operand of 'is' must be a class or trait, got 2."));
+}
+
+#[test]
+fn is_checks_identity_and_traits() {
+    let output = rlox(
+        "is_traits.lox",
+        &[],
+        "fun declare() {\n\
+         \x20 class Twin { name() { return \"twin\"; } }\n\
+         \x20 return Twin;\n\
+         }\n\
+         var first = declare();\n\
+         print first() is first;\n\
+         print first() is declare();\n\
+         print DateTime(2024, 1, 1).addDays(1) is DateTime;\n\
+         trait Walks { walk() {} }\n\
+         trait Swims { swim() {} }\n\
+         class Animal with Walks {}\n\
+         class Dog < Animal {}\n\
+         print Dog() is Walks;\n\
+         print Dog() is Swims;\n\
+         print 1 is Walks;\n",
+    );
+
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "true\nfalse\ntrue\ntrue\nfalse\nfalse\n"
+    );
+}
+
+#[test]
+fn classes_named_like_built_in_types() {
+    let output = rlox(
+        "is_shadowed.lox",
+        &[],
+        "class Number {}\n\
+         print 1 is Number;\n\
+         print Number() is Number;\n\
+         print \"a\" is String;\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "false\ntrue\ntrue\n"
+    );
+}
+
+#[test]
+fn private_members() {
+    let output = rlox(
+        "private.lox",
+        &[],
+        "class Account {\n\
+         \x20 init(balance) { this.#balance = balance; }\n\
+         \x20 deposit(n) { this.#log(\"deposit\"); this.#balance = this.#balance + n; }\n\
+         \x20 balance { return this.#balance; }\n\
+         \x20 #log(what) { print what; }\n\
+         }\n\
+         var account = Account(10);\n\
+         account.deposit(5);\n\
+         print account.balance;\n",
+    );
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "deposit\n15\n");
+
+    for (name, source, error) in [
+        (
+            "private_outside.lox",
+            "class A { init() { this.#x = 1; } }\nprint A().#x;\n",
+            "Can't use private member '#x' outside of the class that declares it.",
+        ),
+        (
+            "private_other.lox",
+            "class A { init() { this.#x = 1; } same(other) { return other.#x; } }\n",
+            "Private member '#x' can only be used through 'this'.",
+        ),
+        (
+            "private_subclass.lox",
+            "class A { init() { this.#x = 1; } }\n\
+             class B < A { x() { return this.#x; } }\n",
+            "Class B doesn't declare private member '#x'.",
+        ),
+    ] {
+        let output = rlox(name, &[], source);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8(output.stderr).unwrap().contains(error));
+    }
+}
+
+#[test]
+fn private_members_belong_to_their_class() {
+    let output = rlox(
+        "private_scoped.lox",
+        &[],
+        "class A {\n\
+         \x20 init() { this.#x = 1; }\n\
+         \x20 ax() { return this.#x; }\n\
+         \x20 #name() { return \"A\"; }\n\
+         \x20 aName() { return this.#name(); }\n\
+         }\n\
+         class B < A {\n\
+         \x20 init() { super.init(); this.#x = 2; }\n\
+         \x20 bx() { return this.#x; }\n\
+         \x20 #name() { return \"B\"; }\n\
+         \x20 bName() { return this.#name(); }\n\
+         }\n\
+         var b = B();\n\
+         print b.ax();\n\
+         print b.bx();\n\
+         print b.aName() + b.bName();\n\
+         print A().ax();\n",
+    );
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n2\nAB\n1\n");
+}
+
+#[test]
+fn private_members_are_scoped_inside_nested_code() {
+    let output = rlox(
+        "private_nested.lox",
+        &[],
+        "enum E { On, Off }\n\
+         class A {\n\
+         \x20 init() { this.#x = 1; }\n\
+         \x20 ax() { return this.#x; }\n\
+         }\n\
+         class B < A {\n\
+         \x20 init() {\n\
+         \x20   super.init();\n\
+         \x20   fun set() { this.#x = [2, {\"k\": this.#x = 0}]; }\n\
+         \x20   set();\n\
+         \x20   match (E.On) { E.On => this.#x[0] = this.#x[0] + 1; E.Off => print 0; }\n\
+         \x20 }\n\
+         \x20 bx() {\n\
+         \x20   class C { init() { this.#x = \"C\"; } cx() { return this.#x; } }\n\
+         \x20   print C().cx();\n\
+         \x20   return this.#x[0];\n\
+         \x20 }\n\
+         }\n\
+         var b = B();\n\
+         print b.ax();\n\
+         print b.bx();\n",
+    );
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\nC\n3\n");
+}
@@ -0,0 +1,46 @@
+// Checks what scripts can see of the command line and environment.
+
+mod common;
+
+#[test]
+fn args_are_passed_to_the_script() {
+    let script = common::script(
+        "args.lox",
+        "var a = args();\n\
+         print len(a);\n\
+         for (var i = 0; i < len(a); i = i + 1) print a[i];\n\
+         print a;\n",
+    );
+    let output = common::command()
+        .arg(script)
+        .args(["x", "-O", "y z"])
+        .output()
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "3\nx\n-O\ny z\n[\"x\", \"-O\", \"y z\"]\n"
+    );
+}
+
+#[test]
+fn getenv_exit_and_stderr() {
+    let script = common::script(
+        "process.lox",
+        "print getenv(\"RLOX_GREETING\");\n\
+         print getenv(\"RLOX_NOT_SET\");\n\
+         stderr(\"warning\");\n\
+         exit(3);\n\
+         print \"unreachable\";\n",
+    );
+    let output = common::command()
+        .arg(script)
+        .env("RLOX_GREETING", "hello")
+        .env_remove("RLOX_NOT_SET")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "hello\nnil\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "warning\n");
+}
@@ -0,0 +1,91 @@
+// Runs the rlox binary for the integration tests. Each test file only uses
+// some of these.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+/// The rlox binary, reporting panics without a backtrace.
+pub fn command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rlox"));
+    command.env("RUST_BACKTRACE", "0");
+    command
+}
+
+/// Runs `source` as a script called `name`, with `args` before it on the
+/// command line.
+pub fn rlox(name: &str, args: &[&str], source: &str) -> Output {
+    run(args, &script(name, source))
+}
+
+/// Runs the script at `file`, with `args` before it on the command line.
+pub fn run(args: &[&str], file: &Path) -> Output {
+    command().args(args).arg(file).output().unwrap()
+}
+
+/// Runs rlox with `args` in the directory `dir`.
+pub fn run_in(dir: &Path, args: &[&str]) -> Output {
+    command().args(args).current_dir(dir).output().unwrap()
+}
+
+/// Writes `source` to a file called `name` in the temporary directory.
+pub fn script(name: &str, source: &str) -> PathBuf {
+    let file = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&file, source).unwrap();
+    file
+}
+
+/// An empty directory called `name` in the temporary directory, holding only
+/// `files`, as pairs of their names and contents.
+pub fn directory(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for (name, contents) in files {
+        fs::write(dir.join(name), contents).unwrap();
+    }
+    dir
+}
+
+/// Runs rlox with `args` and `input` on its stdin.
+pub fn with_input(args: &[&str], input: &str) -> Output {
+    let mut child = command()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+/// Every `.lox` file under `dir`, sorted.
+pub fn lox_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            files.extend(lox_files(&path));
+        } else if path.extension().is_some_and(|e| e == "lox") {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    files
+}
@@ -0,0 +1,236 @@
+// Runs the Crafting Interpreters test corpus in test/ against the rlox binary
+// and compares the results with the checked-in report in test/CONFORMANCE.md.
+//
+// Regenerate the report with:
+//
+//     UPDATE_CONFORMANCE=1 cargo test --test conformance
+//
+// Notes written in the report's last column are kept when it is regenerated.
+
+use std::{collections::BTreeMap, env, fs, path::Path};
+
+mod common;
+
+const REPORT: &str = "test/CONFORMANCE.md";
+
+/// The parts of the upstream corpus left out of `test/`, and why.
+const NOT_VENDORED: &str = "
+## Not vendored
+
+- `benchmark/` times programs rather than checking what they print.
+- `scanning/` and `expressions/` check the token and syntax tree output of
+  the book's early chapters, which only its chapter builds of jlox print.
+- `limit/` cases other than `stack_overflow.lox` test the bytecode limits of
+  clox, such as 256 constants, locals or upvalues, which a tree-walking
+  interpreter doesn't have.
+";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Outcome {
+    Ok,
+    CompileError,
+    RuntimeError,
+    Crash,
+}
+
+struct Expectation {
+    output: Vec<String>,
+    outcome: Outcome,
+}
+
+struct Case {
+    name: String,
+    passed: bool,
+    detail: String,
+}
+
+#[test]
+fn conformance() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let files = common::lox_files(&root.join("test"));
+
+    let cases: Vec<Case> = files.iter().map(|file| run_case(root, file)).collect();
+
+    let report_path = root.join(REPORT);
+    let previous = fs::read_to_string(&report_path).unwrap_or_default();
+    let notes = parse_notes(&previous);
+    let report = render_report(&cases, &notes);
+
+    if env::var_os("UPDATE_CONFORMANCE").is_some() {
+        fs::write(&report_path, &report).unwrap();
+        return;
+    }
+
+    let expected = parse_statuses(&previous);
+    let mut mismatches = Vec::new();
+
+    for case in cases.iter() {
+        match expected.get(&case.name) {
+            Some(&passed) if passed == case.passed => {}
+            Some(&passed) => mismatches.push(format!(
+                "{}: expected {} but {} ({})",
+                case.name,
+                status(passed),
+                status(case.passed),
+                case.detail
+            )),
+            None => mismatches.push(format!("{}: missing from {}", case.name, REPORT)),
+        }
+    }
+
+    for name in expected.keys() {
+        if !cases.iter().any(|c| &c.name == name) {
+            mismatches.push(format!("{}: listed in {} but not found", name, REPORT));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "conformance changed, rerun with UPDATE_CONFORMANCE=1 if this is deliberate:\n{}",
+        mismatches.join("\n")
+    );
+}
+
+fn run_case(root: &Path, file: &Path) -> Case {
+    let name = file
+        .strip_prefix(root.join("test"))
+        .unwrap()
+        .to_string_lossy()
+        .replace('\\', "/");
+    let expectation = parse_expectation(&fs::read_to_string(file).unwrap());
+
+    let output = common::run(&[], file);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<String> = stdout.lines().map(String::from).collect();
+
+    let outcome = match output.status.code() {
+        Some(0) => Outcome::Ok,
+        Some(101) | None => Outcome::Crash,
+        _ if stderr.contains("Runtime Error") => Outcome::RuntimeError,
+        _ => Outcome::CompileError,
+    };
+
+    let detail = match (outcome == expectation.outcome, lines == expectation.output) {
+        (true, true) => String::new(),
+        (false, _) => format!(
+            "expected {:?}, got {:?}: {}",
+            expectation.outcome,
+            outcome,
+            last_error_line(&stderr)
+        ),
+        (true, false) => first_output_difference(&expectation.output, &lines),
+    };
+
+    Case {
+        name,
+        passed: detail.is_empty(),
+        detail,
+    }
+}
+
+fn last_error_line(stderr: &str) -> &str {
+    stderr
+        .lines()
+        .rfind(|l| !l.trim().is_empty() && !l.starts_with("note:"))
+        .unwrap_or("")
+        .trim()
+}
+
+fn parse_expectation(source: &str) -> Expectation {
+    let mut output = Vec::new();
+    let mut outcome = Outcome::Ok;
+
+    for line in source.lines() {
+        if let Some((_, expected)) = line.split_once("// expect: ") {
+            output.push(expected.to_string());
+        } else if line.contains("// expect runtime error: ") {
+            outcome = Outcome::RuntimeError;
+        } else if line.contains("// Error")
+            || line.contains("// [line ")
+            || line.contains("// [java line ")
+        {
+            outcome = Outcome::CompileError;
+        }
+    }
+
+    // Compile errors abort before anything is printed.
+    if outcome == Outcome::CompileError {
+        output.clear();
+    }
+
+    Expectation { output, outcome }
+}
+
+fn first_output_difference(expected: &[String], actual: &[String]) -> String {
+    for i in 0..expected.len().max(actual.len()) {
+        let e = expected.get(i).map_or("<nothing>", |s| s.as_str());
+        let a = actual.get(i).map_or("<nothing>", |s| s.as_str());
+
+        if e != a {
+            return format!("expected `{}`, got `{}`", e, a);
+        }
+    }
+
+    unreachable!()
+}
+
+fn status(passed: bool) -> &'static str {
+    match passed {
+        true => "pass",
+        false => "fail",
+    }
+}
+
+fn table_rows(report: &str) -> impl Iterator<Item = Vec<&str>> {
+    report
+        .lines()
+        .filter(|l| l.starts_with("| `"))
+        .map(|l| l.trim_matches('|').split(" | ").map(str::trim).collect())
+}
+
+fn parse_statuses(report: &str) -> BTreeMap<String, bool> {
+    table_rows(report)
+        .map(|row| (row[0].trim_matches('`').to_string(), row[1] == "pass"))
+        .collect()
+}
+
+fn parse_notes(report: &str) -> BTreeMap<String, String> {
+    table_rows(report)
+        .filter(|row| row.len() > 2 && !row[2].is_empty())
+        .map(|row| (row[0].trim_matches('`').to_string(), row[2].to_string()))
+        .collect()
+}
+
+fn render_report(cases: &[Case], notes: &BTreeMap<String, String>) -> String {
+    let passed = cases.iter().filter(|c| c.passed).count();
+    let mut report = String::new();
+
+    report.push_str("# Conformance\n\n");
+    report.push_str("Results of running the Crafting Interpreters test corpus in `test/`.\n");
+    report.push_str("Generated by `UPDATE_CONFORMANCE=1 cargo test --test conformance`.\n");
+    report.push_str("Cases pass when the printed output and the kind of error (compile or\n");
+    report.push_str("runtime) match; error message wording is not compared.\n\n");
+    report.push_str(&format!("{} of {} cases pass.\n\n", passed, cases.len()));
+    report.push_str("| Case | Status | Notes |\n");
+    report.push_str("| ---- | ------ | ----- |\n");
+
+    for case in cases {
+        let note = match (case.passed, notes.get(&case.name)) {
+            (true, _) => String::new(),
+            (false, Some(note)) => note.clone(),
+            (false, None) => case.detail.replace('|', "\\|"),
+        };
+
+        report.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            case.name,
+            status(case.passed),
+            note
+        ));
+    }
+
+    report.push_str(NOT_VENDORED);
+    report
+}
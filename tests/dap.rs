@@ -0,0 +1,215 @@
+// Drives `rlox debug` the way an editor would, waiting for each event before
+// answering it, and checks where the script stops.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Stdio},
+};
+
+mod common;
+
+const SCRIPT: &str = "var a = 1;\nprint a;\nfun f(x) {\n  return x + 1;\n}\nprint f(a);\n";
+
+#[test]
+fn stops_at_breakpoints_and_continues() {
+    let script = common::script("dap_breakpoints.lox", SCRIPT);
+    let mut client = Client::launch(&[], &script);
+    // the same file, named differently from the launch request
+    let same_file = script
+        .parent()
+        .unwrap()
+        .join(".")
+        .join("dap_breakpoints.lox");
+    client.configure(&same_file, &[2, 4]);
+
+    let stopped = client.wait_for(r#""event":"stopped""#);
+    assert!(stopped.contains(r#""reason":"breakpoint""#));
+    client.request("stackTrace", r#"{"threadId":1}"#);
+    let trace = client.wait_for(r#""command":"stackTrace""#);
+    assert!(
+        trace.contains(r#""name":"<script>","line":2,"#),
+        "{}",
+        trace
+    );
+
+    client.request("continue", r#"{"threadId":1}"#);
+    client.wait_for(r#""event":"stopped""#);
+    client.request("stackTrace", r#"{"threadId":1}"#);
+    let trace = client.wait_for(r#""command":"stackTrace""#);
+    assert!(trace.contains(r#""name":"f","line":4,"#), "{}", trace);
+
+    client.request("continue", r#"{"threadId":1}"#);
+    let rest = client.read_until(r#""event":"terminated""#);
+    assert!(rest.iter().any(|m| m.contains(r#""output":"2\n""#)));
+    assert!(rest.iter().any(|m| m.contains(r#""exitCode":0"#)));
+    assert!(!rest.iter().any(|m| m.contains(r#""event":"stopped""#)));
+
+    client.disconnect();
+}
+
+#[test]
+fn stops_once_on_a_line_of_nested_statements() {
+    let script = common::script(
+        "dap_nested.lox",
+        "var a = 1;\nif (a > 0) { print a; }\nprint a + 1;\n",
+    );
+    let mut client = Client::launch(&[], &script);
+    client.configure(&script, &[2]);
+
+    client.wait_for(r#""event":"stopped""#);
+    client.request("continue", r#"{"threadId":1}"#);
+    let rest = client.read_until(r#""event":"terminated""#);
+    assert!(!rest.iter().any(|m| m.contains(r#""event":"stopped""#)));
+    assert!(rest.iter().any(|m| m.contains(r#""output":"2\n""#)));
+
+    client.disconnect();
+}
+
+#[test]
+fn ignores_breakpoints_in_other_sources() {
+    let script = common::script("dap_other_source.lox", SCRIPT);
+    let mut client = Client::launch(&[], &script);
+    client.configure(Path::new("/elsewhere/other.lox"), &[2, 4]);
+
+    let messages = client.read_until(r#""event":"terminated""#);
+    assert!(!messages.iter().any(|m| m.contains(r#""event":"stopped""#)));
+
+    client.disconnect();
+}
+
+#[test]
+fn runs_with_command_line_options() {
+    let script = common::script("dap_options.lox", SCRIPT);
+    let mut client = Client::launch(&["--max-steps=2"], &script);
+    client.configure(&script, &[]);
+
+    let messages = client.read_until(r#""event":"terminated""#);
+    assert!(messages
+        .iter()
+        .any(|m| m.contains("Step budget of 2 exceeded.")));
+    assert!(messages.iter().any(|m| m.contains(r#""exitCode":1"#)));
+
+    client.disconnect();
+}
+
+/// An editor talking to `rlox debug` over its stdin and stdout.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    seq: usize,
+}
+
+impl Client {
+    /// Starts the adapter with `args` before `debug`, initializes it and asks
+    /// it to launch `script`.
+    fn launch(args: &[&str], script: &Path) -> Client {
+        let mut child = common::command()
+            .args(args)
+            .arg("debug")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let mut client = Client {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+            seq: 0,
+        };
+
+        client.request("initialize", r#"{"adapterID":"rlox"}"#);
+        client.wait_for(r#""event":"initialized""#);
+        client.request(
+            "launch",
+            &format!(r#"{{"program":"{}"}}"#, script.display()),
+        );
+        client.wait_for(r#""command":"launch""#);
+        client
+    }
+
+    /// Sets breakpoints on `lines` of `source` and lets the script start.
+    fn configure(&mut self, source: &Path, lines: &[usize]) {
+        let breakpoints: Vec<String> = lines
+            .iter()
+            .map(|line| format!(r#"{{"line":{}}}"#, line))
+            .collect();
+        self.request(
+            "setBreakpoints",
+            &format!(
+                r#"{{"source":{{"path":"{}"}},"breakpoints":[{}]}}"#,
+                source.display(),
+                breakpoints.join(",")
+            ),
+        );
+        self.wait_for(r#""command":"setBreakpoints""#);
+        self.request("configurationDone", "{}");
+    }
+
+    fn disconnect(mut self) {
+        self.request("disconnect", "{}");
+        self.wait_for(r#""command":"disconnect""#);
+        assert!(self.child.wait().unwrap().success());
+    }
+
+    fn request(&mut self, command: &str, arguments: &str) {
+        self.seq += 1;
+        let message = format!(
+            r#"{{"seq":{},"type":"request","command":"{}","arguments":{}}}"#,
+            self.seq, command, arguments
+        );
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )
+        .unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    /// Skips messages up to the first one containing `needle` and returns it.
+    fn wait_for(&mut self, needle: &str) -> String {
+        self.read_until(needle).pop().unwrap()
+    }
+
+    /// Every message up to and including the first one containing `needle`.
+    fn read_until(&mut self, needle: &str) -> Vec<String> {
+        let mut messages = Vec::new();
+
+        loop {
+            let message = self.read();
+            let found = message.contains(needle);
+            messages.push(message);
+            if found {
+                return messages;
+            }
+        }
+    }
+
+    fn read(&mut self) -> String {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            assert!(
+                self.stdout.read_line(&mut line).unwrap() > 0,
+                "the adapter exited"
+            );
+            match line.trim_end() {
+                "" => break,
+                header => {
+                    if let Some(value) = header.strip_prefix("Content-Length: ") {
+                        length = Some(value.parse().unwrap());
+                    }
+                }
+            }
+        }
+
+        let mut body = vec![0; length.unwrap()];
+        self.stdout.read_exact(&mut body).unwrap();
+        String::from_utf8(body).unwrap()
+    }
+}
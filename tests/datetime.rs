@@ -0,0 +1,62 @@
+// Checks the DateTime class and sleep().
+
+mod common;
+
+use common::rlox;
+
+#[test]
+fn dates_and_times() {
+    let output = rlox(
+        "datetime.lox",
+        &[],
+        "var d = DateTime(2024, 2, 29, 13, 5, 7.25);\n\
+         print d.format();\n\
+         print d.weekday();\n\
+         var p = DateTime(\"2024-03-10T08:00:00+02:00\");\n\
+         print p.toUtc().format();\n\
+         print p.toOffset(-5.5).format();\n\
+         print p.format(\"%a %d %b %Y %H:%M %z\");\n\
+         print d.add(3600).format();\n\
+         print d.addMonths(12).format();\n\
+         print p.since(DateTime(\"2024-03-10\"));\n\
+         print d.isBefore(p);\n\
+         print p.equals(DateTime(\"2024-03-10T06:00Z\"));\n\
+         class Meeting < DateTime {}\n\
+         print Meeting(2024, 1, 1).year();\n\
+         DateTime(2023, 2, 29);\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "2024-02-29T13:05:07.25Z\n\
+         4\n\
+         2024-03-10T06:00:00Z\n\
+         2024-03-10T00:30:00-05:30\n\
+         Sun 10 Mar 2024 08:00 +02:00\n\
+         2024-02-29T14:05:07.25Z\n\
+         2025-02-28T13:05:07.25Z\n\
+         21600\n\
+         true\n\
+         true\n\
+         2024\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("Runtime Error: Day 29 is out of range for February 2023.\n"));
+}
+
+#[test]
+fn sleep_moves_the_virtual_clock() {
+    let output = rlox(
+        "sleep.lox",
+        &["--deterministic", "--clock-per-call=0"],
+        "sleep(90);\n\
+         print clock();\n\
+         print DateTime().format();\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "90\n1970-01-01T00:01:30Z\n"
+    );
+}
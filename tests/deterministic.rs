@@ -0,0 +1,36 @@
+// Checks that --deterministic makes clock() and the random natives print the
+// same every run.
+
+mod common;
+
+const SCRIPT: &str = "var start = clock();\n\
+                      for (var i = 0; i < 10; i = i + 1) {}\n\
+                      print clock() - start;\n\
+                      print randomInt(1, 1000000);\n\
+                      print keys({\"b\": 1, \"a\": 2, 3: 4});\n";
+
+#[test]
+fn deterministic_runs_print_the_same() {
+    let first = common::rlox("deterministic.lox", &["--deterministic"], SCRIPT);
+    let second = common::rlox("deterministic.lox", &["--deterministic"], SCRIPT);
+
+    assert_eq!(first.status.code(), Some(0));
+    assert_eq!(first.stdout, second.stdout);
+    assert!(String::from_utf8(first.stdout)
+        .unwrap()
+        .starts_with("0.001\n"));
+}
+
+#[test]
+fn virtual_clock_advances_per_call_and_step() {
+    let output = common::rlox(
+        "clock_per_step.lox",
+        &["--clock-per-call=0", "--clock-per-step=0.5"],
+        SCRIPT,
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    // every statement executed between the two calls adds half a second
+    assert_eq!(stdout.lines().next(), Some("17.0"));
+    assert!(stdout.ends_with("[\"b\", \"a\", 3]\n"));
+}
@@ -0,0 +1,48 @@
+// Checks the syntax tree and token dumps printed by `rlox --dump-ast` and
+// `rlox --dump-tokens`.
+
+mod common;
+
+const SOURCE: &str = "class A < B {\n  m(x) {\n    this.y = -x;\n    return super.m(x) or \"s\";\n  }\n}\nfor (var i = 0; i < 2; i = i + 1) print f(i, nil);\n";
+
+#[test]
+fn dump_ast_as_s_expressions() {
+    assert_eq!(
+        dump("--dump-ast", SOURCE),
+        "(class A (< B) (method m (x) (expr (set this y (- x))) (return (or (call (super m) x) \"s\"))))\n\
+         (block (var i 0) (while (< i 2) (block (print (call f i nil)) (expr (assign i (+ i 1))))))\n"
+    );
+}
+
+#[test]
+fn dump_ast_as_json() {
+    let json = dump("--dump-ast=json", "var a;\nprint a + 1;\n");
+
+    assert!(json.starts_with(
+        "[{\"node\":\"Var\",\"id\":1,\"span\":{\"start\":1,\"end\":1},\
+         \"name\":{\"type\":\"Identifier\",\"lexeme\":\"a\",\"line\":1,\"column\":5},\
+         \"initializer\":{\"node\":\"Literal\",\"id\":0,\"span\":{\"start\":1,\"end\":1},\
+         \"synthetic\":true,\"value\":null}},"
+    ));
+    assert!(json.contains("\"node\":\"Binary\""));
+    assert!(json.contains("\"span\":{\"start\":2,\"end\":2}"));
+}
+
+#[test]
+fn dump_tokens() {
+    assert_eq!(
+        dump("--dump-tokens", "print 1;"),
+        "(Print \"print\" 1:1)\n(Number \"1\" 1:7)\n(Semicolon \";\" 1:8)\n(Eof \"\" 1:9)\n"
+    );
+    assert_eq!(
+        dump("--dump-tokens=json", "nil"),
+        "[{\"type\":\"Nil\",\"lexeme\":\"nil\",\"line\":1,\"column\":1},\
+         {\"type\":\"Eof\",\"lexeme\":\"\",\"line\":1,\"column\":4}]\n"
+    );
+}
+
+fn dump(flag: &str, source: &str) -> String {
+    let output = common::with_input(&[flag], source);
+    assert!(output.status.success(), "{} failed", flag);
+    String::from_utf8(output.stdout).unwrap()
+}
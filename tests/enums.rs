@@ -0,0 +1,166 @@
+// Checks enum declarations and their variants.
+
+mod common;
+
+use common::rlox;
+
+#[test]
+fn variants() {
+    let output = rlox(
+        "enums.lox",
+        &[],
+        "enum Color { Red, Green, Blue }\n\
+         print Color.Red;\n\
+         print Color.Green.name;\n\
+         print Color.Blue.ordinal;\n\
+         print Color.Red == Color.Red;\n\
+         print Color.Red == Color.Green;\n\
+         print Color.Red is Color;\n\
+         var colors = Color.values();\n\
+         for (var i = 0; i < len(colors); i = i + 1) print colors[i].name;\n\
+         colors[0] = nil;\n\
+         print Color.values();\n\
+         var hex = {Color.Red: \"#f00\"};\n\
+         hex[Color.Green] = \"#0f0\";\n\
+         print hex[Color.Red];\n\
+         print hex;\n\
+         Color();\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Color.Red\nGreen\n2\ntrue\nfalse\ntrue\nRed\nGreen\nBlue\n\
+         [Color.Red, Color.Green, Color.Blue]\n#f00\n\
+         {Color.Red: \"#f00\", Color.Green: \"#0f0\"}\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Can't make new variants of enum Color."));
+}
+
+#[test]
+fn variant_names_are_unique() {
+    let output = rlox("enum_duplicates.lox", &[], "enum Color { Red, Red }\n");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Enum Color has more than one variant called Red."));
+}
+
+#[test]
+fn variants_can_not_change() {
+    let rename = rlox(
+        "enum_rename.lox",
+        &[],
+        "enum Color { Red, Green }\nColor.Red.name = \"Blue\";\n",
+    );
+    assert!(String::from_utf8(rename.stderr)
+        .unwrap()
+        .contains("Can't set fields on a variant of enum Color."));
+
+    let rebind = rlox(
+        "enum_rebind.lox",
+        &[],
+        "enum Color { Red, Green }\nColor.Red = Color.Green;\n",
+    );
+    assert!(String::from_utf8(rebind.stderr)
+        .unwrap()
+        .contains("Can't set fields on enum Color."));
+}
+
+#[test]
+fn trailing_comma() {
+    let output = rlox(
+        "enum_trailing_comma.lox",
+        &[],
+        "enum Color {\n  Red,\n  Green,\n}\nprint Color.values();\n",
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[Color.Red, Color.Green]\n"
+    );
+
+    let output = rlox("enum_only_comma.lox", &[], "enum Color { , }\n");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn match_statements() {
+    let output = rlox(
+        "enum_match.lox",
+        &[],
+        "enum Light { Red, Amber, Green }\n\
+         fun next(light) {\n\
+         \x20 match (light) {\n\
+         \x20   Light.Red => return Light.Green;\n\
+         \x20   Light.Amber => return Light.Red;\n\
+         \x20   Light.Green => { return Light.Amber; }\n\
+         \x20 }\n\
+         }\n\
+         print next(Light.Red);\n\
+         print next(Light.Green);\n\
+         match (Light.Red) {\n\
+         \x20 Light.Green => print \"go\";\n\
+         \x20 else => print \"wait\";\n\
+         }\n\
+         print next(\"Red\");\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Light.Green\nLight.Amber\nwait\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("No arm of the match covers Red."));
+}
+
+#[test]
+fn matches_without_else_cover_every_variant() {
+    let errors = [
+        (
+            "match (Light.Red) { Light.Red => print 1; Light.Green => print 2; }",
+            "Match over Light doesn't cover Amber.",
+        ),
+        (
+            "match (Light.Red) { Light.Blue => print 1; else => print 2; }",
+            "Enum Light has no variant Blue.",
+        ),
+        (
+            "match (Light.Red) { Light.Red => print 1; Light.Red => print 2; else => print 3; }",
+            "Light.Red is matched more than once.",
+        ),
+        (
+            "match (1) { Other.Red => print 1; }",
+            "Other is not an enum.",
+        ),
+        ("match (1) {}", "A match needs at least one arm."),
+        (
+            "match (Light.Red) { Light.Red => print 1; Walk.Red => print 2; else => print 3; }",
+            "A match can only have variants of one enum.",
+        ),
+        (
+            "match (Walk.Red) { Light.Red => print 1; else => print 2; }",
+            "Can't match Walk.Red against variants of Light.",
+        ),
+    ];
+
+    for (i, (source, error)) in errors.iter().enumerate() {
+        let output = rlox(
+            &format!("enum_match_error_{}.lox", i),
+            &[],
+            &format!(
+                "enum Light {{ Red, Amber, Green }}\nenum Walk {{ Red, Green }}\n{}\n",
+                source
+            ),
+        );
+
+        assert_eq!(output.status.code(), Some(1), "{}", source);
+        assert!(
+            String::from_utf8(output.stderr).unwrap().contains(error),
+            "{}",
+            source
+        );
+    }
+}
@@ -0,0 +1,134 @@
+// Round-trips every script in examples/ through `rlox fmt` and checks that
+// formatting is stable, keeps every comment and doesn't change what the
+// script does.
+
+use std::{fs, path::Path};
+
+mod common;
+
+use common::lox_files;
+
+#[test]
+fn examples_round_trip() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fmt");
+    fs::create_dir_all(&out_dir).unwrap();
+
+    for file in lox_files(&root.join("examples")) {
+        let name = file.strip_prefix(root).unwrap().display().to_string();
+        let source = fs::read_to_string(&file).unwrap();
+
+        let formatted = common::with_input(&["fmt"], &source);
+        assert!(formatted.status.success(), "{}: failed to format", name);
+        let formatted = String::from_utf8(formatted.stdout).unwrap();
+
+        assert_eq!(
+            String::from_utf8(common::with_input(&["fmt"], &formatted).stdout).unwrap(),
+            formatted,
+            "{}: formatting is not stable",
+            name
+        );
+        assert_eq!(
+            source.matches("//").count(),
+            formatted.matches("//").count(),
+            "{}: comments were lost",
+            name
+        );
+
+        let formatted_file = out_dir.join(file.file_name().unwrap());
+        fs::write(&formatted_file, &formatted).unwrap();
+
+        // what clock() prints differs between runs anyway
+        if !source.contains("clock()") {
+            let before = common::run(&[], &file);
+            let after = common::run(&[], &formatted_file);
+            assert_eq!(before.status.code(), after.status.code(), "{}", name);
+            assert_eq!(before.stdout, after.stdout, "{}: output changed", name);
+        }
+
+        let check = common::run(&["fmt", "--check"], &formatted_file);
+        assert!(
+            check.status.success(),
+            "{}: --check rejected its own output",
+            name
+        );
+    }
+}
+
+#[test]
+fn check_fails_on_unformatted_source() {
+    let output = common::with_input(&["fmt", "--check"], "var a=1;\n");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = common::with_input(&["fmt", "--check"], "var a = 1;\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn keeps_comments_and_desugared_syntax() {
+    let source = "// leading\n\
+                  for(;;){print 1;} // after the loop\n\n\n\
+                  var a;\n\
+                  fun f(){\n\
+                  return; // nothing\n\
+                  }\n\
+                  if(a)print a;else{print 2;}\n\
+                  // trailing\n";
+
+    let expected = "// leading\n\
+                    for (;;) {\n  print 1;\n} // after the loop\n\n\
+                    var a;\n\
+                    fun f() {\n  return; // nothing\n}\n\
+                    if (a) print a;\nelse {\n  print 2;\n}\n\
+                    // trailing\n";
+
+    let output = common::with_input(&["fmt"], source);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+#[test]
+fn formats_match_arms() {
+    let source = "enum E{A,B}\n\
+                  match(E.A){E.A=>print 1;\n\
+                  // only B\n\
+                  E.B=>{print 2;}else=>print 3;}\n";
+
+    let expected = "enum E { A, B }\n\
+                    match (E.A) {\n  E.A => print 1;\n  // only B\n  \
+                    E.B => {\n    print 2;\n  }\n  else => print 3;\n}\n";
+
+    let output = common::with_input(&["fmt"], source);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+#[test]
+fn keeps_members_on_one_line_in_source_order() {
+    let source = "class A { b() {} class c() {} a { return 1; } set d(v) {} e() {} }\n";
+
+    let expected = "class A {\n  b() {}\n  class c() {}\n  a {\n    return 1;\n  }\n  \
+                    set d(v) {}\n  e() {}\n}\n";
+
+    let output = common::with_input(&["fmt"], source);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+#[test]
+fn keeps_comments_inside_expressions_in_place() {
+    let source = "var x = 1 + // one\n\
+                  2;\n\
+                  print f(x, // two\n\
+                  [\n\
+                  // three\n\
+                  3]);\n";
+
+    let expected = "var x = 1 + // one\n  2;\n\
+                    print f(x, // two\n  [// three\n  3]);\n";
+
+    let output = common::with_input(&["fmt"], source);
+    let formatted = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(
+        String::from_utf8(common::with_input(&["fmt"], &formatted).stdout).unwrap(),
+        formatted
+    );
+}
@@ -0,0 +1,52 @@
+// Checks the file system natives, and that --no-fs turns them off.
+
+use std::path::Path;
+
+mod common;
+
+const SCRIPT: &str = "mkdir(\"out/nested\");\n\
+                      writeFile(\"out/a.txt\", \"one\n\");\n\
+                      appendFile(\"out/a.txt\", \"two\n\");\n\
+                      print readFile(\"out/a.txt\") == \"one\ntwo\n\";\n\
+                      print exists(\"out/a.txt\");\n\
+                      print listDir(\"out\");\n\
+                      var f = openFile(\"out/a.txt\");\n\
+                      print f.path;\n\
+                      var line = f.readLine();\n\
+                      while (line != nil) {\n\
+                      \x20 print line;\n\
+                      \x20 line = f.readLine();\n\
+                      }\n\
+                      f.close();\n\
+                      removeFile(\"out/a.txt\");\n\
+                      print exists(\"out/a.txt\");\n\
+                      readFile(\"out/missing.txt\");\n";
+
+#[test]
+fn reads_and_writes_files() {
+    let output = common::run_in(&common::directory("fs", &[("fs.lox", SCRIPT)]), &["fs.lox"]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "true\ntrue\n[\"a.txt\", \"nested\"]\nout/a.txt\none\ntwo\nfalse\n"
+    );
+    assert!(String::from_utf8(output.stderr).unwrap().contains(
+        "Runtime Error: Failed to read file 'out/missing.txt': No such file or directory"
+    ));
+}
+
+#[test]
+fn no_fs_turns_file_access_off() {
+    let output = common::run_in(
+        &common::directory("no_fs", &[("fs.lox", SCRIPT)]),
+        &["--no-fs", "fs.lox"],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("Runtime Error: Native function 'mkdir' is not allowed.\n"));
+    assert!(!Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("no_fs/out")
+        .exists());
+}
@@ -0,0 +1,33 @@
+// Checks that scripts read their input with input() and readLine(), and that
+// the REPL shares stdin with the script it runs.
+
+mod common;
+
+#[test]
+fn reads_lines_from_stdin() {
+    let file = common::script(
+        "io.lox",
+        "var name = input(\"Name? \");\n\
+         print \"Hello, \" + name + \"!\";\n\
+         var line = readLine();\n\
+         while (line != nil) {\n\
+         \x20 print line;\n\
+         \x20 line = readLine();\n\
+         }\n",
+    );
+
+    let output = common::with_input(&[file.to_str().unwrap()], "Ada\r\none\ntwo");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Name? Hello, Ada!\none\ntwo\n"
+    );
+}
+
+#[test]
+fn repl_shares_stdin_with_the_script() {
+    let output = common::with_input(&[], "print readLine();\ninput for the script\nprint 1;\n");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "input for the script\n1\n"
+    );
+}
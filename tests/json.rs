@@ -0,0 +1,155 @@
+// Checks maps and converting values to and from JSON.
+
+mod common;
+
+const DATA: &str = r#"{"name": "lox", "price": 0.10, "big": 12345678901234567890.5,
+  "tags": ["a", true, null], "quote": "say \"hi\"\n"}"#;
+
+const SCRIPT: &str = "var data = json.parse(readFile(\"data.json\"));\n\
+                      print data;\n\
+                      print data[\"price\"] + 0.20;\n\
+                      print data[\"missing\"];\n\
+                      print keys(data);\n\
+                      var m = {\"x\": 1, \"y\": [1, 2]};\n\
+                      m[\"z\"] = {};\n\
+                      m[\"x\"] = 3;\n\
+                      print len(m);\n\
+                      print json.stringify(m);\n\
+                      print json.stringify(m, 2);\n\
+                      class Point { init(x, y) { this.x = x; this.y = y; } }\n\
+                      print json.stringify(Point(1, nil));\n\
+                      print json.stringify(data) == json.stringify(json.parse(json.stringify(data)));\n";
+
+#[test]
+fn converts_values_to_and_from_json() {
+    let output = common::run_in(
+        &common::directory("json", &[("data.json", DATA), ("json.lox", SCRIPT)]),
+        &["json.lox"],
+    );
+
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"name\": \"lox\", \"price\": 0.10, \"big\": 12345678901234567890.5, \
+         \"tags\": [\"a\", true, nil], \"quote\": \"say \\\"hi\\\"\\n\"}\n\
+         0.30\n\
+         nil\n\
+         [\"name\", \"price\", \"big\", \"tags\", \"quote\"]\n\
+         3\n\
+         {\"x\":3,\"y\":[1,2],\"z\":{}}\n\
+         {\n  \"x\": 3,\n  \"y\": [\n    1,\n    2\n  ],\n  \"z\": {}\n}\n\
+         {\"x\":1,\"y\":null}\n\
+         true\n"
+    );
+}
+
+#[test]
+fn reports_where_json_is_malformed() {
+    let output = common::run_in(
+        &common::directory(
+            "json_malformed",
+            &[
+                ("data.json", "{\"a\": 1,\n  \"b\": tru}"),
+                ("json.lox", "json.parse(readFile(\"data.json\"));"),
+            ],
+        ),
+        &["json.lox"],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("Runtime Error: Invalid JSON: Expected 'true' at line 2 column 12.\n"));
+}
+
+#[test]
+fn follows_the_json_grammar_for_numbers_and_strings() {
+    let script = "print len(json.parse(readFile(\"data.json\")));";
+    let output = common::run_in(
+        &common::directory(
+            "json_grammar",
+            &[
+                ("data.json", "[0, -0.5, 10, 1E2, 2e-3, \"a\\tb\"]"),
+                ("json.lox", script),
+            ],
+        ),
+        &["json.lox"],
+    );
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "6\n");
+
+    for (data, error) in [
+        ("01", "Invalid number '01' at line 1 column 1"),
+        ("[-01]", "Invalid number '-01' at line 1 column 2"),
+        ("1.", "Invalid number '1.' at line 1 column 1"),
+        ("+1", "Unexpected character '+' at line 1 column 1"),
+        ("1e", "Invalid number '1e' at line 1 column 1"),
+        (
+            "\"a\tb\"",
+            "Unescaped control character in string at line 1 column 3",
+        ),
+        (
+            "\"\u{0}\"",
+            "Unescaped control character in string at line 1 column 2",
+        ),
+    ] {
+        let output = common::run_in(
+            &common::directory("json_grammar", &[("data.json", data), ("json.lox", script)]),
+            &["json.lox"],
+        );
+
+        assert!(
+            String::from_utf8(output.stderr)
+                .unwrap()
+                .ends_with(&format!("Runtime Error: Invalid JSON: {}.\n", error)),
+            "{}",
+            data
+        );
+    }
+}
+
+#[test]
+fn rejects_json_nested_too_deeply() {
+    let output = common::run_in(
+        &common::directory(
+            "json_nested",
+            &[
+                ("data.json", &"[".repeat(100_000)),
+                ("json.lox", "json.parse(readFile(\"data.json\"));"),
+            ],
+        ),
+        &["json.lox"],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().ends_with(
+        "Runtime Error: Invalid JSON: Arrays and objects nest more than 512 deep at line 1 column 513.\n"
+    ));
+}
+
+#[test]
+fn rejects_values_without_json() {
+    for (script, error) in [
+        (
+            "json.stringify(clock);",
+            "Can't convert <native fn> to JSON.",
+        ),
+        (
+            "var l = [1]; l[0] = l; json.stringify(l);",
+            "Can't convert a value that contains itself to JSON.",
+        ),
+        (
+            "json.stringify({1: 2});",
+            "JSON object keys must be strings, got 1.",
+        ),
+    ] {
+        let output = common::run_in(
+            &common::directory("json_invalid", &[("data.json", DATA), ("json.lox", script)]),
+            &["json.lox"],
+        );
+
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .ends_with(&format!("Runtime Error: {}\n", error)));
+    }
+}
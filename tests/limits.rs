@@ -0,0 +1,155 @@
+// Checks that each resource limit stops a script that runs into it with its
+// own error.
+
+use std::{
+    process::Output,
+    time::{Duration, Instant},
+};
+
+mod common;
+
+use common::rlox;
+
+#[test]
+fn step_budget() {
+    let output = rlox("steps.lox", &["--max-steps=1000"], "while (true) {}\n");
+    assert_error(output, "Step budget of 1000 exceeded.");
+}
+
+#[test]
+fn timeout() {
+    let output = rlox("timeout.lox", &["--timeout=100"], "while (true) {}\n");
+    assert_error(output, "Timed out after 100ms.");
+}
+
+#[test]
+fn timeout_cuts_sleep_short() {
+    let started = Instant::now();
+    let output = rlox("sleep.lox", &["--timeout=100"], "sleep(60);\nprint 1;\n");
+    assert_error(output, "Timed out after 100ms.");
+    assert!(started.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn heap_size() {
+    let output = rlox(
+        "heap.lox",
+        &["--max-heap=100"],
+        "class Node {}\n\
+         var list = nil;\n\
+         while (true) {\n\
+         \x20 var node = Node();\n\
+         \x20 node.next = list;\n\
+         \x20 list = node;\n\
+         }\n",
+    );
+    assert_error(output, "Heap limit of 100 objects exceeded.");
+}
+
+#[test]
+fn garbage_cycles_do_not_count_towards_the_heap_size() {
+    let output = rlox(
+        "heap_cycles.lox",
+        &["--max-heap=100", "--max-steps=10000"],
+        "class Node {}\n\
+         while (true) {\n\
+         \x20 var a = Node();\n\
+         \x20 var b = Node();\n\
+         \x20 a.b = b;\n\
+         \x20 b.a = a;\n\
+         }\n",
+    );
+    assert_error(output, "Step budget of 10000 exceeded.");
+}
+
+#[test]
+fn string_length() {
+    let output = rlox(
+        "string.lox",
+        &["--max-string-length=1000"],
+        "var s = \"ab\";\nwhile (true) s = s + s;\n",
+    );
+    assert_error(output, "String longer than 1000 characters.");
+}
+
+#[test]
+fn string_length_covers_every_string_made() {
+    let output = rlox(
+        "long_literal.lox",
+        &["--max-string-length=5"],
+        "print 1;\nprint \"abcdef\";\n",
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert_error(output, "String longer than 5 characters.");
+
+    let output = common::with_input(
+        &["-O", "--max-string-length=5", "--dump-ast"],
+        "print \"abc\" + \"def\";\nprint \"ab\" + \"c\";\n",
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "(print (+ \"abc\" \"def\"))\n(print \"abc\")\n"
+    );
+
+    let script = common::script("long_args.lox", "print args();\n");
+    let output = common::command()
+        .arg("--max-string-length=5")
+        .arg(script)
+        .args(["a", "abcdef"])
+        .output()
+        .unwrap();
+    assert_error(output, "String longer than 5 characters.");
+}
+
+#[test]
+fn native_allow_list() {
+    let source = "print gc();\nprint clock() > 0;\n";
+
+    let output = rlox("natives.lox", &["--allow-natives=gc"], source);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n");
+    assert_error(output, "Native function 'clock' is not allowed.");
+
+    let output = rlox("natives.lox", &["--allow-natives=gc,clock"], source);
+    assert!(output.status.success());
+}
+
+#[test]
+fn native_allow_list_covers_classes_and_methods() {
+    for (source, error) in [
+        ("print DateTime();\n", "DateTime.init"),
+        ("print DateTime(2020, 1, 2);\n", "DateTime.init"),
+        ("print openFile(\"x\");\n", "openFile"),
+    ] {
+        let output = rlox("class_natives.lox", &["--allow-natives=len"], source);
+        assert_error(
+            output,
+            &format!("Native function '{}' is not allowed.", error),
+        );
+    }
+
+    let output = rlox(
+        "class_natives.lox",
+        &["--allow-natives=DateTime.init,DateTime.year"],
+        "var d = DateTime(2020, 1, 2);\nprint d.year();\nprint d.format(\"%Y\");\n",
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2020\n");
+    assert_error(output, "Native function 'DateTime.format' is not allowed.");
+
+    // enums only need the natives that come with them
+    let output = rlox(
+        "enum_natives.lox",
+        &["--allow-natives=len"],
+        "enum E { A, B }\nprint E.values();\nprint E.B;\nprint len(E.values());\n",
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[E.A, E.B]\nE.B\n2\n"
+    );
+}
+
+fn assert_error(output: Output, error: &str) {
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with(&format!("Runtime Error: {}\n", error)));
+}
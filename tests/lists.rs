@@ -0,0 +1,47 @@
+// Checks list literals, indexing and len().
+
+mod common;
+
+use common::rlox;
+
+#[test]
+fn lists() {
+    let output = rlox(
+        "lists.lox",
+        &[],
+        "var l = [1, \"two\", [nil]];\n\
+         l[0] = l[0] + 1;\n\
+         print l;\n\
+         print l[2][0];\n\
+         print len(l) + len(\"four\");\n\
+         print [1, 2] == [1, 2];\n\
+         print l == l;\n\
+         print l[3];\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[2, \"two\", [nil]]\nnil\n7\nfalse\ntrue\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("Runtime Error: List index 3 out of range.\n"));
+}
+
+#[test]
+fn prints_a_list_that_contains_itself() {
+    let output = rlox(
+        "lists_cycle.lox",
+        &[],
+        "var l = [1, 2];\n\
+         l[0] = l;\n\
+         print l;\n\
+         print [l, l];\n\
+         print l == l[0];\n",
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[[...], 2]\n[[[...], 2], [[...], 2]]\ntrue\n"
+    );
+}
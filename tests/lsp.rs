@@ -0,0 +1,163 @@
+// Drives `rlox lsp` over stdin and checks the messages it sends back.
+
+mod common;
+
+#[test]
+fn reports_scanner_errors_without_crashing() {
+    let output = lsp(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+        &did_open("file:///number.lox", "var x = 1."),
+        &did_open("file:///comment.lox", "// café\\nvar y = \\\"naïve\\\";"),
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]);
+
+    assert!(output
+        .contains(r#""uri":"file:///number.lox","diagnostics":[{"range":{"start":{"line":0,"#));
+    assert!(output.contains(r#""uri":"file:///comment.lox","diagnostics":[]"#));
+    assert!(output.contains(r#"{"jsonrpc":"2.0","id":2,"result":null}"#));
+}
+
+#[test]
+fn completes_every_keyword() {
+    let output = lsp(&[
+        &did_open("file:///a.lox", "var a;\\n"),
+        r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/completion","params":{"textDocument":{"uri":"file:///a.lox"},"position":{"line":1,"character":0}}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]);
+
+    for keyword in ["class", "enum", "is", "trait", "while", "with"] {
+        assert!(
+            output.contains(&format!(r#"{{"label":"{}","kind":14}}"#, keyword)),
+            "{} is missing",
+            keyword
+        );
+    }
+}
+
+#[test]
+fn completes_native_globals() {
+    let output = lsp(&[
+        &did_open("file:///a.lox", "var a;\\n"),
+        r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/completion","params":{"textDocument":{"uri":"file:///a.lox"},"position":{"line":1,"character":0}}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]);
+
+    for item in [
+        r#"{"label":"len","kind":3}"#,
+        r#"{"label":"keys","kind":3}"#,
+        r#"{"label":"DateTime","kind":7}"#,
+        r#"{"label":"json","kind":6}"#,
+    ] {
+        assert!(output.contains(item), "{} is missing", item);
+    }
+}
+
+#[test]
+fn completes_the_methods_of_the_receiver() {
+    let completion = |id: usize, line: usize, character: usize| {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/completion","params":{{"textDocument":{{"uri":"file:///a.lox"}},"position":{{"line":{},"character":{}}}}}}}"#,
+            id, line, character
+        )
+    };
+    let output = lsp(&[
+        &did_open(
+            "file:///a.lox",
+            "class A { a() {} }\\n\
+             class B < A { b() { this.b(); } }\\n\
+             class C { c() {} }\\n\
+             var x = B();\\n\
+             x.a();\\n",
+        ),
+        &completion(1, 1, 25),
+        &completion(2, 4, 2),
+        r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]);
+
+    for id in [1, 2] {
+        let response = response(&output, id);
+        assert!(
+            response.contains(r#"{"label":"a","kind":2}"#),
+            "{}",
+            response
+        );
+        assert!(
+            response.contains(r#"{"label":"b","kind":2}"#),
+            "{}",
+            response
+        );
+        assert!(
+            !response.contains(r#"{"label":"c","kind":2}"#),
+            "{}",
+            response
+        );
+    }
+}
+
+#[test]
+fn counts_characters_in_utf16_code_units() {
+    let output = lsp(&[
+        &did_open("file:///a.lox", "var b = 1;\\nprint \\\"😀😀\\\" + b;\\n"),
+        r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.lox"},"position":{"line":1,"character":15}}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/references","params":{"textDocument":{"uri":"file:///a.lox"},"position":{"line":0,"character":4}}}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]);
+
+    assert!(response(&output, 1)
+        .contains(r#""range":{"start":{"line":0,"character":4},"end":{"line":0,"character":5}}"#));
+    assert!(response(&output, 2).contains(
+        r#""range":{"start":{"line":1,"character":15},"end":{"line":1,"character":16}}"#
+    ));
+}
+
+#[test]
+fn answers_unreadable_messages_with_an_error() {
+    let output = lsp(&[
+        "{not json",
+        r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]);
+
+    assert!(output.contains(r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"#));
+    assert!(output.contains(r#"{"jsonrpc":"2.0","id":1,"result":null}"#));
+}
+
+/// The response to the request with `id` in everything the server wrote.
+fn response(output: &str, id: usize) -> &str {
+    let start = output
+        .find(&format!(r#""id":{},"#, id))
+        .unwrap_or_else(|| panic!("no response to {}", id));
+    let end = output[start..]
+        .find("Content-Length")
+        .map_or(output.len(), |end| start + end);
+    &output[start..end]
+}
+
+fn did_open(uri: &str, text: &str) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","text":"{}"}}}}}}"#,
+        uri, text
+    )
+}
+
+/// Sends each of `messages` with its header and returns everything the server
+/// wrote, after checking that it exited cleanly.
+fn lsp(messages: &[&str]) -> String {
+    let input: String = messages
+        .iter()
+        .map(|m| format!("Content-Length: {}\r\n\r\n{}", m.len(), m))
+        .collect();
+
+    let output = common::with_input(&["lsp"], &input);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
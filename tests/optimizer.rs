@@ -0,0 +1,63 @@
+// Checks that `rlox -O` folds constants and drops dead branches without
+// changing what any script in examples/ or test/ does.
+
+use std::path::Path;
+
+mod common;
+
+use common::lox_files;
+
+#[test]
+fn optimised_scripts_behave_the_same() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files = lox_files(&root.join("examples"));
+    files.extend(lox_files(&root.join("test")));
+
+    for file in files {
+        // scripts that read clock() print the same each run
+        let plain = common::run(&["--deterministic"], &file);
+        let optimised = common::run(&["--deterministic", "-O"], &file);
+        let name = file.strip_prefix(root).unwrap().display();
+
+        assert_eq!(plain.status.code(), optimised.status.code(), "{}", name);
+        assert_eq!(plain.stdout, optimised.stdout, "{}: output changed", name);
+    }
+}
+
+#[test]
+fn folds_constants_and_dead_branches() {
+    let output = common::rlox(
+        "optimizer.lox",
+        &["-O", "--dump-ast"],
+        "print 60 * 60 * 24;\n\
+         print \"a\" + \"b\" == \"ab\";\n\
+         if (false) { print 1; } else { print 2; }\n\
+         while (false) print 3;\n\
+         print nil or x;\n\
+         print -(2 - 3) < !nil;\n\
+         print 1 / 0;\n",
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "(print 86400)\n\
+         (print true)\n\
+         (block (print 2))\n\
+         (block)\n\
+         (print x)\n\
+         (print (< 1 true))\n\
+         (print (/ 1 0))\n"
+    );
+}
+
+#[test]
+fn reports_errors_in_removed_branches() {
+    let output = common::rlox(
+        "optimizer_errors.lox",
+        &["-O"],
+        "if (false) { return 1; }\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Cannot return from top-level code."));
+}
@@ -0,0 +1,75 @@
+// Checks that the random natives repeat themselves for the same seed, whether
+// it comes from --seed or seed().
+
+mod common;
+
+use common::rlox;
+
+const SCRIPT: &str = "print random();\n\
+                      print randomInt(1, 6);\n\
+                      var l = [1, 2, 3, 4, 5];\n\
+                      shuffle(l);\n\
+                      print l;\n\
+                      print choice(l);\n";
+
+#[test]
+fn same_seed_same_numbers() {
+    let first = rlox("seeded.lox", &["--seed=42"], SCRIPT);
+    let second = rlox("seeded.lox", &["--seed=42"], SCRIPT);
+    let other = rlox("seeded.lox", &["--seed=43"], SCRIPT);
+
+    assert_eq!(first.status.code(), Some(0));
+    assert_eq!(first.stdout, second.stdout);
+    assert_ne!(first.stdout, other.stdout);
+
+    let reseeded = rlox(
+        "reseeded.lox",
+        &[],
+        &format!("seed(42);\n{}seed(42);\n{}", SCRIPT, SCRIPT),
+    );
+    let stdout = String::from_utf8(first.stdout).unwrap();
+    assert_eq!(
+        String::from_utf8(reseeded.stdout).unwrap(),
+        stdout.clone() + &stdout
+    );
+}
+
+#[test]
+fn seeds_are_whole_and_not_negative() {
+    let largest = rlox("seed_max.lox", &[], "seed(18446744073709551615);\n");
+    assert_eq!(largest.status.code(), Some(0));
+
+    for seed in ["-1", "1.5", "18446744073709551616", "\"1\""] {
+        let output = rlox("seed_invalid.lox", &[], &format!("seed({});\n", seed));
+
+        assert!(
+            String::from_utf8(output.stderr).unwrap().contains(
+                "Runtime Error: Seed must be a whole number from 0 to 18446744073709551615, got "
+            ),
+            "{}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn numbers_stay_in_range() {
+    let output = rlox(
+        "range.lox",
+        &[],
+        "var ok = true;\n\
+         for (var i = 0; i < 1000; i = i + 1) {\n\
+         \x20 var r = random();\n\
+         \x20 var n = randomInt(-2, 2);\n\
+         \x20 if (r < 0 or r >= 1 or n < -2 or n > 2) ok = false;\n\
+         }\n\
+         print ok;\n\
+         print randomInt(3, 3);\n\
+         choice([]);\n",
+    );
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "true\n3\n");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("Runtime Error: Can't choose from an empty list.\n"));
+}